@@ -97,6 +97,9 @@ use crate::{
 pub const NO_IO_BYTE: u8 = 0xFF; // This is the byte read from a unconnected IO address.
 pub const OPEN_BUS_BYTE: u8 = 0xFF; // This is the byte read from an unmapped memory address.
 
+/// Maximum number of IO accesses retained in the IO trace. Older entries are discarded.
+pub const IO_TRACE_LEN: usize = 0x10000;
+
 const ADDRESS_SPACE: usize = 0x10_0000;
 const DEFAULT_WAIT_STATES: u32 = 0;
 
@@ -267,6 +270,7 @@ impl MemRangeDescriptor {
     }
 }
 
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum IoDeviceType {
     A0Register,
     Ppi,
@@ -283,6 +287,32 @@ pub enum IoDeviceType {
     Ems,
     GamePort,
     Video(VideoCardId),
+    Custom(usize),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum IoTraceDirection {
+    In,
+    Out,
+}
+
+/// A single IO port access captured while IO tracing is enabled.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct IoTraceEntry {
+    /// System ticks elapsed since tracing was enabled.
+    pub sys_tick: u64,
+    pub direction: IoTraceDirection,
+    pub port: u16,
+    pub value: u8,
+}
+
+/// A summary of the IO ports registered on the bus, for diagnosing machine configurations.
+#[derive(Clone, Debug, Default)]
+pub struct IoPortReport {
+    /// Each registered port and the name of the device that owns it, sorted by port.
+    pub ports: Vec<(u16, String)>,
+    /// Ports that more than one device tried to claim, as (port, owner, other claimant).
+    pub conflicts: Vec<(u16, String, String)>,
 }
 
 pub enum IoDeviceDispatch {
@@ -384,6 +414,10 @@ pub struct BusInterface {
 
     io_map: FxHashMap<u16, IoDeviceType>,
     io_desc_map: FxHashMap<u16, String>,
+    io_conflicts: Vec<(u16, String, String)>,
+    io_trace_enabled: bool,
+    io_trace_ticks: u64,
+    io_trace: VecDeque<IoTraceEntry>,
     io_stats: FxHashMap<u16, (bool, IoDeviceStats)>,
     ppi: Option<Ppi>,
     a0: Option<A0Register>,
@@ -403,6 +437,7 @@ pub struct BusInterface {
     ems: Option<LotechEmsCard>,
     cart_slot: Option<CartridgeSlot>,
     game_port: Option<GamePort>,
+    custom_io_devices: Vec<Option<Box<dyn IoDevice>>>,
 
     videocards:    FxHashMap<VideoCardId, VideoCardDispatch>,
     videocard_ids: Vec<VideoCardId>,
@@ -426,8 +461,17 @@ pub struct BusInterface {
 macro_rules! add_io_device {
     ($self:expr, $device:expr, $device_type:expr) => {{
         let port_list = $device.port_list();
-        $self.io_desc_map.extend(port_list.iter().map(|p| (p.1, p.0.clone())));
-        $self.io_map.extend(port_list.into_iter().map(|p| (p.1, $device_type)));
+        for (name, port) in port_list {
+            // The last device to claim a port receives its IO, but keep a record of the overlap.
+            if let Some(prev) = $self.io_map.insert(port, $device_type) {
+                if prev != $device_type {
+                    let prev_name = $self.io_desc_map.get(&port).cloned().unwrap_or_else(|| format!("{:?}", prev));
+                    log::warn!("IO port {:04X} claimed by {} is reassigned to {}", port, prev_name, name);
+                    $self.io_conflicts.push((port, prev_name, name.clone()));
+                }
+            }
+            $self.io_desc_map.insert(port, name);
+        }
     }};
 }
 
@@ -554,6 +598,10 @@ impl Default for BusInterface {
 
             io_map: FxHashMap::default(),
             io_desc_map: FxHashMap::default(),
+            io_conflicts: Vec::new(),
+            io_trace_enabled: false,
+            io_trace_ticks: 0,
+            io_trace: VecDeque::new(),
             io_stats: FxHashMap::default(),
             ppi: None,
             a0: None,
@@ -573,6 +621,7 @@ impl Default for BusInterface {
             ems: None,
             cart_slot: None,
             game_port: None,
+            custom_io_devices: Vec::new(),
             videocards: FxHashMap::default(),
             videocard_ids: Vec::new(),
 
@@ -789,6 +838,71 @@ impl BusInterface {
         self.memory[start..start + len].to_vec()
     }
 
+    /// Write the bytes in `data` directly into RAM starting at linear address `address`.
+    ///
+    /// This is intended for test setup and memory pokes. It does not obey memory mapping or ROM
+    /// protection and incurs no wait states, so memory-mapped devices will not see the write.
+    pub fn write_bytes(&mut self, address: usize, data: &[u8]) -> Result<(), MemError> {
+        let end = address
+            .checked_add(data.len())
+            .filter(|end| *end <= self.memory.len())
+            .ok_or(MemError::WriteOutOfBoundsError)?;
+        self.memory[address..end].copy_from_slice(data);
+        Ok(())
+    }
+
+    /// Load a raw binary image into RAM starting at linear address `address`.
+    ///
+    /// Unlike write_bytes(), the image may not overlap ROM or a memory-mapped device, so that a
+    /// test program can't silently patch a BIOS or land in video memory.
+    pub fn load_image(&mut self, image: &[u8], address: usize) -> Result<(), MemError> {
+        let end = address
+            .checked_add(image.len())
+            .filter(|end| *end <= self.memory.len())
+            .ok_or(MemError::WriteOutOfBoundsError)?;
+        if self.memory_mask[address..end]
+            .iter()
+            .any(|m| *m & (MEM_ROM_BIT | MEM_MMIO_BIT) != 0)
+        {
+            return Err(MemError::ProtectedRegionError);
+        }
+        self.memory[address..end].copy_from_slice(image);
+        Ok(())
+    }
+
+    /// Read a raw binary image from `path` and load it with load_image(). Returns the size of the image.
+    pub fn load_image_file(&mut self, path: &Path, address: usize) -> Result<usize, MemError> {
+        let image = std::fs::read(path).map_err(|e| {
+            log::error!("Error reading image file {:?}: {}", path, e);
+            MemError::FileReadError
+        })?;
+        self.load_image(&image, address)?;
+        Ok(image.len())
+    }
+
+    /// Fill `len` bytes of RAM starting at linear address `address` with `byte`.
+    ///
+    /// Like write_bytes(), this bypasses memory-mapped devices and ROM protection.
+    pub fn fill(&mut self, address: usize, len: usize, byte: u8) -> Result<(), MemError> {
+        let end = address
+            .checked_add(len)
+            .filter(|end| *end <= self.memory.len())
+            .ok_or(MemError::WriteOutOfBoundsError)?;
+        self.memory[address..end].fill(byte);
+        Ok(())
+    }
+
+    /// Read `len` bytes of RAM starting at linear address `address`.
+    ///
+    /// This reads the backing memory directly, so memory-mapped devices are not consulted.
+    pub fn read_bytes(&self, address: usize, len: usize) -> Result<Vec<u8>, MemError> {
+        let end = address
+            .checked_add(len)
+            .filter(|end| *end <= self.memory.len())
+            .ok_or(MemError::ReadOutOfBoundsError)?;
+        Ok(self.memory[address..end].to_vec())
+    }
+
     pub fn set_descriptor(&mut self, start: usize, size: usize, cycle_cost: u32, read_only: bool) {
         // TODO: prevent overlapping descriptors
         self.desc_vec.push({
@@ -1964,7 +2078,13 @@ impl BusInterface {
                 }
                 #[cfg(feature = "ega")]
                 VideoType::EGA => {
-                    let ega = EGACard::new(TraceLogger::None, clock_mode, video_frame_debug, card.dip_switch);
+                    let ega = EGACard::new(
+                        TraceLogger::None,
+                        clock_mode,
+                        video_frame_debug,
+                        card.dip_switch,
+                        card.vram_size.map(|s| s as usize),
+                    );
                     add_io_device!(self, ega, IoDeviceType::Video(video_id));
                     add_mmio_device!(self, ega, MmioDeviceType::Video(video_id));
                     video_dispatch = VideoCardDispatch::Ega(ega)
@@ -2035,6 +2155,10 @@ impl BusInterface {
     ) -> Option<DeviceEvent> {
         let mut event = None;
 
+        if self.io_trace_enabled {
+            self.io_trace_ticks += sys_ticks as u64;
+        }
+
         if let Some(keyboard) = &mut self.keyboard {
             // Send keyboard events to devices.
             if let Some(kb_event) = kb_event_opt {
@@ -2524,12 +2648,21 @@ impl BusInterface {
                         }
                     }
                 }
+                IoDeviceType::Custom(idx) => {
+                    if let Some(Some(device)) = self.custom_io_devices.get_mut(*idx) {
+                        byte = Some(device.read_u8(port, nul_delta));
+                    }
+                }
                 _ => {}
             }
         }
 
         let byte_val = byte.unwrap_or(NO_IO_BYTE);
 
+        if self.io_trace_enabled {
+            self.record_io_trace(IoTraceDirection::In, port, byte_val, sys_ticks);
+        }
+
         self.io_stats
             .entry(port)
             .and_modify(|e| {
@@ -2689,10 +2822,22 @@ impl BusInterface {
                         }
                     }
                 }
+                IoDeviceType::Custom(idx) => {
+                    let idx = *idx;
+                    if let Some(mut device) = self.custom_io_devices.get_mut(idx).and_then(|d| d.take()) {
+                        device.write_u8(port, data, Some(self), nul_delta);
+                        resolved = true;
+                        self.custom_io_devices[idx] = Some(device);
+                    }
+                }
                 _ => {}
             }
         }
 
+        if self.io_trace_enabled {
+            self.record_io_trace(IoTraceDirection::Out, port, data, sys_ticks);
+        }
+
         self.io_stats
             .entry(port)
             .and_modify(|e| {
@@ -2702,6 +2847,116 @@ impl BusInterface {
             .or_insert((resolved, IoDeviceStats::one_read()));
     }
 
+    /// Register an additional IO device at runtime.
+    ///
+    /// The device will receive reads and writes for each port in `ports`. If any of the ports are
+    /// already claimed by another device, nothing is registered and every conflicting port is
+    /// returned as an error.
+    ///
+    /// Ports are described as "Custom device N: <name>", where N is the device's registration
+    /// index and the name comes from its port_list(), so that devices sharing port names can be
+    /// told apart in the port report.
+    pub fn register_io_device(&mut self, device: Box<dyn IoDevice>, ports: &[u16]) -> Result<(), Vec<u16>> {
+        let idx = self.custom_io_devices.len();
+        let port_names = device.port_list();
+        let port_name = |port: u16| match port_names.iter().find(|(_, p)| *p == port) {
+            Some((name, _)) => format!("Custom device {}: {}", idx, name),
+            None => format!("Custom device {}", idx),
+        };
+
+        let conflicts: Vec<u16> = ports
+            .iter()
+            .copied()
+            .filter(|p| self.io_map.contains_key(p))
+            .collect();
+        if !conflicts.is_empty() {
+            for port in &conflicts {
+                let owner = self
+                    .io_desc_map
+                    .get(port)
+                    .cloned()
+                    .unwrap_or_else(|| format!("{:?}", self.io_map[port]));
+                self.io_conflicts.push((*port, owner, port_name(*port)));
+            }
+            return Err(conflicts);
+        }
+
+        for port in ports {
+            self.io_desc_map.insert(*port, port_name(*port));
+            self.io_map.insert(*port, IoDeviceType::Custom(idx));
+        }
+        self.custom_io_devices.push(Some(device));
+        Ok(())
+    }
+
+    /// Enable or disable capture of all IO port reads and writes. Enabling the trace clears any
+    /// previously captured entries and restarts the trace clock.
+    /// Only the last IO_TRACE_LEN accesses are retained.
+    pub fn set_io_trace(&mut self, enabled: bool) {
+        if enabled && !self.io_trace_enabled {
+            self.io_trace.clear();
+            self.io_trace_ticks = 0;
+        }
+        self.io_trace_enabled = enabled;
+    }
+
+    pub fn io_trace_enabled(&self) -> bool {
+        self.io_trace_enabled
+    }
+
+    fn record_io_trace(&mut self, direction: IoTraceDirection, port: u16, value: u8, sys_ticks: u32) {
+        if self.io_trace.len() >= IO_TRACE_LEN {
+            self.io_trace.pop_front();
+        }
+        self.io_trace.push_back(IoTraceEntry {
+            sys_tick: self.io_trace_ticks + sys_ticks as u64,
+            direction,
+            port,
+            value,
+        });
+    }
+
+    /// Take the captured IO trace, leaving it empty. Tracing remains enabled if it was enabled.
+    pub fn take_io_trace(&mut self) -> Vec<IoTraceEntry> {
+        std::mem::take(&mut self.io_trace).into()
+    }
+
+    /// Write the captured IO trace as CSV, with a header row.
+    pub fn write_io_trace_csv(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        writeln!(writer, "sys_tick,direction,port,value")?;
+        for entry in &self.io_trace {
+            let direction = match entry.direction {
+                IoTraceDirection::In => "IN",
+                IoTraceDirection::Out => "OUT",
+            };
+            writeln!(writer, "{},{},{:04X},{:02X}", entry.sys_tick, direction, entry.port, entry.value)?;
+        }
+        Ok(())
+    }
+
+    /// Produce a report of all registered IO ports and their owning devices, along with any ports
+    /// that were claimed by more than one device during machine installation or runtime registration.
+    pub fn io_port_report(&self) -> IoPortReport {
+        let mut ports: Vec<(u16, String)> = self
+            .io_map
+            .iter()
+            .map(|(port, device)| {
+                let name = self
+                    .io_desc_map
+                    .get(port)
+                    .cloned()
+                    .unwrap_or_else(|| format!("{:?}", device));
+                (*port, name)
+            })
+            .collect();
+        ports.sort_by_key(|(port, _)| *port);
+
+        IoPortReport {
+            ports,
+            conflicts: self.io_conflicts.clone(),
+        }
+    }
+
     /// Return a boolean indicating whether a timer interrupt is imminent.
     /// This is intended to be called by the CPU to determine the required cycle granularity of the HLT state.
     #[inline]
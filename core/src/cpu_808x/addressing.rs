@@ -133,6 +133,12 @@ impl Intel808x {
     /// Load the EA operand for the current instruction, if applicable
     /// (not all instructions with a mod r/m will load, ie, write-only instructions)
     pub fn load_operand(&mut self) {
+        // Decode-only mode skips instructions that don't transfer control, so don't make the
+        // memory or MMIO read their operand would.
+        if self.decode_only && !Intel808x::is_control_flow(self.i.mnemonic) {
+            return;
+        }
+
         if DECODE[self.i.decode_idx].gdr.loads_ea() {
             // This instruction loads its EA operand. Load and save into OPR.
 
@@ -119,24 +119,25 @@ impl Intel808x {
     }
 
     /// Ascii adjust before Division
-    /// Flags: The SF, ZF, and PF flags are set according to the resulting binary value in the AL register
+    /// Flags: The SF, ZF, and PF flags are set according to the resulting binary value in the AL register.
+    /// CF, OF and AF are undefined, but on the 8088 reflect the addition of the product to AL.
     pub fn aad(&mut self, imm8: u8) {
         cycles_mc!(self, 0x170, 0x171, MC_JUMP);
         let product_native = (self.a.h() as u16).wrapping_mul(imm8 as u16) as u8;
         let (_, product) = 0u8.corx(self, self.a.h() as u16, imm8 as u16, false);
         assert_eq!((product as u8), product_native);
 
-        self.set_register8(Register8::AL, self.a.l().wrapping_add(product as u8));
+        // Other sources set flags from AX register. Intel's documentation specifies AL
+        let sum = self.math_op8(Mnemonic::ADD, self.a.l(), product as u8);
+        self.set_register8(Register8::AL, sum);
         self.set_register8(Register8::AH, 0);
 
         cycles_mc!(self, 0x172, 0x173);
-
-        // Other sources set flags from AX register. Intel's documentation specifies AL
-        self.set_szp_flags_from_result_u8(self.a.l());
     }
 
     /// DAA — Decimal Adjust AL after Addition
-    /// Flags: The SF, ZF, and PF flags are set according to the result. OF is undefined.
+    /// Flags: The SF, ZF, and PF flags are set according to the result. OF is undefined, but
+    /// deterministic on the 8088 - it is modeled below from hardware testing.
     /// See https://www.righto.com/2023/01/understanding-x86s-decimal-adjust-after.html for
     /// clarification on intel's pseudocode for this function.
     pub fn daa(&mut self) {
@@ -186,7 +187,8 @@ impl Intel808x {
     }
 
     /// DAS — Decimal Adjust AL after Subtraction
-    /// Flags: The SF, ZF, and PF flags are set according to the result.
+    /// Flags: The SF, ZF, and PF flags are set according to the result. OF is undefined, but
+    /// deterministic on the 8088 - it is modeled below from hardware testing.
     pub fn das(&mut self) {
         let old_al = self.a.l();
         let old_af = self.get_flag(Flag::AuxCarry);
@@ -262,3 +264,201 @@ impl Intel808x {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set_bcd_state(cpu: &mut Intel808x, ax: u16, af: bool, cf: bool) {
+        cpu.set_register16(Register16::AX, ax);
+        cpu.set_flag_state(Flag::AuxCarry, af);
+        cpu.set_flag_state(Flag::Carry, cf);
+    }
+
+    #[test]
+    fn test_daa_sweep() {
+        let mut cpu = Intel808x::default();
+
+        for al in 0..=0xFFu8 {
+            for (af, cf) in [(false, false), (true, false), (false, true), (true, true)] {
+                set_bcd_state(&mut cpu, al as u16, af, cf);
+                cpu.daa();
+
+                let mut expected_al = al;
+                let mut expected_af = false;
+                let mut expected_cf = false;
+                if (al & 0x0F) > 9 || af {
+                    expected_al = expected_al.wrapping_add(6);
+                    expected_af = true;
+                }
+                let al_check = if af { 0x9F } else { 0x99 };
+                if al > al_check || cf {
+                    expected_al = expected_al.wrapping_add(0x60);
+                    expected_cf = true;
+                }
+
+                assert_eq!(cpu.a.l(), expected_al, "DAA AL={:02X} AF={} CF={}", al, af, cf);
+                assert_eq!(cpu.get_flag(Flag::AuxCarry), expected_af);
+                assert_eq!(cpu.get_flag(Flag::Carry), expected_cf);
+                assert_eq!(cpu.get_flag(Flag::Zero), expected_al == 0);
+                assert_eq!(cpu.get_flag(Flag::Sign), expected_al & 0x80 != 0);
+                assert_eq!(cpu.get_flag(Flag::Parity), PARITY_TABLE[expected_al as usize]);
+            }
+        }
+
+        // Undefined OF: set when the adjustment carries into the sign bit.
+        set_bcd_state(&mut cpu, 0x7A, false, false);
+        cpu.daa();
+        assert!(cpu.get_flag(Flag::Overflow));
+        set_bcd_state(&mut cpu, 0x1A, false, true);
+        cpu.daa();
+        assert!(cpu.get_flag(Flag::Overflow));
+        set_bcd_state(&mut cpu, 0x1A, false, false);
+        cpu.daa();
+        assert!(!cpu.get_flag(Flag::Overflow));
+    }
+
+    #[test]
+    fn test_das_sweep() {
+        let mut cpu = Intel808x::default();
+
+        for al in 0..=0xFFu8 {
+            for (af, cf) in [(false, false), (true, false), (false, true), (true, true)] {
+                set_bcd_state(&mut cpu, al as u16, af, cf);
+                cpu.das();
+
+                let mut expected_al = al;
+                let mut expected_af = false;
+                let mut expected_cf = false;
+                if (al & 0x0F) > 9 || af {
+                    expected_al = expected_al.wrapping_sub(6);
+                    expected_af = true;
+                }
+                let al_check = if af { 0x9F } else { 0x99 };
+                if al > al_check || cf {
+                    expected_al = expected_al.wrapping_sub(0x60);
+                    expected_cf = true;
+                }
+
+                assert_eq!(cpu.a.l(), expected_al, "DAS AL={:02X} AF={} CF={}", al, af, cf);
+                assert_eq!(cpu.get_flag(Flag::AuxCarry), expected_af);
+                assert_eq!(cpu.get_flag(Flag::Carry), expected_cf);
+                assert_eq!(cpu.get_flag(Flag::Zero), expected_al == 0);
+                assert_eq!(cpu.get_flag(Flag::Sign), expected_al & 0x80 != 0);
+                assert_eq!(cpu.get_flag(Flag::Parity), PARITY_TABLE[expected_al as usize]);
+            }
+        }
+
+        // Undefined OF: set when the adjustment borrows out of the sign bit.
+        set_bcd_state(&mut cpu, 0x80, true, false);
+        cpu.das();
+        assert!(cpu.get_flag(Flag::Overflow));
+        set_bcd_state(&mut cpu, 0x9A, false, false);
+        cpu.das();
+        assert!(cpu.get_flag(Flag::Overflow));
+        set_bcd_state(&mut cpu, 0x00, false, false);
+        cpu.das();
+        assert!(!cpu.get_flag(Flag::Overflow));
+    }
+
+    #[test]
+    fn test_aaa_aas_flags() {
+        let mut cpu = Intel808x::default();
+        // These instructions spend cycles, so give the CPU the 8088's 4-byte queue as the builder would.
+        cpu.queue.set_size(4, 1);
+
+        for al in 0..=0xFFu8 {
+            for af in [false, true] {
+                let adjust = (al & 0x0F) > 9 || af;
+
+                set_bcd_state(&mut cpu, 0x1200 | al as u16, af, false);
+                cpu.aaa();
+                let new_al = if adjust { al.wrapping_add(6) } else { al };
+                assert_eq!(cpu.a.l(), new_al & 0x0F, "AAA AL={:02X} AF={}", al, af);
+                assert_eq!(cpu.a.h(), if adjust { 0x13 } else { 0x12 });
+                assert_eq!(cpu.get_flag(Flag::AuxCarry), adjust);
+                assert_eq!(cpu.get_flag(Flag::Carry), adjust);
+                // ZF and PF reflect the intermediate (unmasked) result on hardware.
+                assert_eq!(cpu.get_flag(Flag::Zero), new_al == 0);
+                assert_eq!(cpu.get_flag(Flag::Parity), PARITY_TABLE[new_al as usize]);
+
+                set_bcd_state(&mut cpu, 0x1200 | al as u16, af, false);
+                cpu.aas();
+                let new_al = if adjust { al.wrapping_sub(6) } else { al };
+                assert_eq!(cpu.a.l(), new_al & 0x0F, "AAS AL={:02X} AF={}", al, af);
+                assert_eq!(cpu.a.h(), if adjust { 0x11 } else { 0x12 });
+                assert_eq!(cpu.get_flag(Flag::AuxCarry), adjust);
+                assert_eq!(cpu.get_flag(Flag::Carry), adjust);
+                assert_eq!(cpu.get_flag(Flag::Zero), new_al == 0);
+                assert_eq!(cpu.get_flag(Flag::Parity), PARITY_TABLE[new_al as usize]);
+            }
+        }
+
+        // Undefined SF and OF as observed on hardware.
+        set_bcd_state(&mut cpu, 0x7A, false, false);
+        cpu.aaa();
+        assert!(cpu.get_flag(Flag::Overflow));
+        assert!(cpu.get_flag(Flag::Sign));
+
+        set_bcd_state(&mut cpu, 0x80, true, false);
+        cpu.aas();
+        assert!(cpu.get_flag(Flag::Overflow));
+        assert!(!cpu.get_flag(Flag::Sign));
+    }
+
+    #[test]
+    fn test_aam_aad_bases() {
+        let mut cpu = Intel808x::default();
+        // These instructions spend cycles, so give the CPU the 8088's 4-byte queue as the builder would.
+        cpu.queue.set_size(4, 1);
+
+        // AAM 16 splits AL into nibbles
+        cpu.set_register16(Register16::AX, 0x005B);
+        assert!(cpu.aam(16));
+        assert_eq!(cpu.a.x(), 0x050B);
+        assert!(!cpu.get_flag(Flag::Sign));
+        assert!(!cpu.get_flag(Flag::Zero));
+        assert!(!cpu.get_flag(Flag::Parity));
+
+        cpu.set_register16(Register16::AX, 0x0040);
+        assert!(cpu.aam(16));
+        assert_eq!(cpu.a.x(), 0x0400);
+        assert!(cpu.get_flag(Flag::Zero));
+        assert!(cpu.get_flag(Flag::Parity));
+
+        // AAM 8 splits AL into octal digits
+        cpu.set_register16(Register16::AX, 0x003F);
+        assert!(cpu.aam(8));
+        assert_eq!(cpu.a.x(), 0x0707);
+        assert!(!cpu.get_flag(Flag::Zero));
+        assert!(!cpu.get_flag(Flag::Parity));
+
+        // A base of 0 is a divide error
+        assert!(!cpu.aam(0));
+
+        // AAD 16 recombines nibbles
+        cpu.set_register16(Register16::AX, 0x050B);
+        cpu.aad(16);
+        assert_eq!(cpu.a.x(), 0x005B);
+        assert!(!cpu.get_flag(Flag::Sign));
+        assert!(!cpu.get_flag(Flag::Zero));
+        assert!(!cpu.get_flag(Flag::Parity));
+        assert!(!cpu.get_flag(Flag::Carry));
+        assert!(!cpu.get_flag(Flag::Overflow));
+
+        // AAD 8 recombines octal digits
+        cpu.set_register16(Register16::AX, 0x0707);
+        cpu.aad(8);
+        assert_eq!(cpu.a.x(), 0x003F);
+        assert!(cpu.get_flag(Flag::Parity));
+
+        // CF, OF and AF follow the addition of the product to AL
+        cpu.set_register16(Register16::AX, 0x0F80);
+        cpu.aad(16);
+        assert_eq!(cpu.a.x(), 0x0070);
+        assert!(cpu.get_flag(Flag::Carry));
+        assert!(cpu.get_flag(Flag::Overflow));
+        assert!(!cpu.get_flag(Flag::AuxCarry));
+        assert!(!cpu.get_flag(Flag::Sign));
+    }
+}
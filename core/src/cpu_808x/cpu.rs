@@ -44,16 +44,21 @@ use crate::{
         TCycle,
         TaCycle,
         CPU_FLAGS_RESERVED_ON,
+        RECENT_TRACE_RESERVE_MAX,
     },
     cpu_common::{
         instruction::Instruction,
+        BusAccessLogEntry,
         Cpu,
         CpuAddress,
         CpuDispatch,
         CpuError,
         CpuOption,
+        CpuProfile,
+        CpuResetState,
         CpuStringState,
         CpuType,
+        InstructionHook,
         QueueOp,
         ServiceEvent,
         StepResult,
@@ -79,6 +84,11 @@ impl Cpu for Intel808x {
         self.set_reset_vector(address);
     }
 
+    #[inline]
+    fn set_reset_state(&mut self, state: CpuResetState) {
+        self.set_reset_state(state);
+    }
+
     #[inline]
     fn set_reset_queue_contents(&mut self, contents: Vec<u8>) {
         self.set_reset_queue_contents(contents)
@@ -176,6 +186,29 @@ impl Cpu for Intel808x {
         self.get_instruction_ct()
     }
 
+    #[inline]
+    fn enable_profiling(&mut self, state: bool) {
+        self.enable_profiling(state);
+    }
+
+    #[inline]
+    fn get_profile(&self) -> &CpuProfile {
+        self.get_profile()
+    }
+
+    #[inline]
+    fn take_decode_trace(&mut self) -> Vec<Instruction> {
+        self.take_decode_trace()
+    }
+
+    fn take_bus_access_log(&mut self) -> Vec<BusAccessLogEntry> {
+        self.take_bus_access_log()
+    }
+
+    fn set_cycle_adjustments(&mut self, table: Option<Box<[u8; 256]>>) {
+        self.set_cycle_adjustments(table)
+    }
+
     /// Return the resolved flat address of CS:CORR(PC)
     #[inline]
     fn flat_ip(&self) -> u32 {
@@ -207,6 +240,14 @@ impl Cpu for Intel808x {
         self.dump_call_stack()
     }
 
+    fn dump_recent_trace(&self) -> String {
+        self.dump_recent_trace()
+    }
+
+    fn get_call_stack(&self) -> Vec<(u16, u16, u16, u16)> {
+        self.get_call_stack()
+    }
+
     #[inline]
     fn get_service_event(&mut self) -> Option<ServiceEvent> {
         self.service_events.pop_front()
@@ -244,6 +285,10 @@ impl Cpu for Intel808x {
         self.set_breakpoints(bp_list)
     }
 
+    fn set_instruction_hook(&mut self, hook: Option<InstructionHook>) {
+        self.instruction_hook = hook;
+    }
+
     #[inline]
     fn get_step_over_breakpoint(&self) -> Option<CpuAddress> {
         self.get_step_over_breakpoint()
@@ -321,6 +366,27 @@ impl Cpu for Intel808x {
                 log::debug!("Setting EnableServiceInterrupt to: {:?}", state);
                 self.enable_service_interrupt = state;
             }
+            CpuOption::RepIterationLimit(limit) => {
+                log::debug!("Setting RepIterationLimit to: {}", limit);
+                self.rep_iteration_limit = limit;
+            }
+            CpuOption::DecodeOnly(state) => {
+                log::debug!("Setting DecodeOnly to: {:?}", state);
+                self.decode_trace.clear();
+                self.decode_only = state;
+            }
+            CpuOption::RecentTraceDepth(depth) => {
+                log::debug!("Setting RecentTraceDepth to: {}", depth);
+                self.recent_trace_depth = depth as usize;
+                self.recent_trace.clear();
+                self.recent_trace.reserve(self.recent_trace_depth.min(RECENT_TRACE_RESERVE_MAX));
+            }
+            CpuOption::BusAccessLogDepth(depth) => {
+                log::debug!("Setting BusAccessLogDepth to: {}", depth);
+                self.bus_log_depth = depth as usize;
+                self.bus_log.clear();
+                self.bus_log.reserve(self.bus_log_depth);
+            }
         }
     }
 
@@ -335,6 +401,10 @@ impl Cpu for Intel808x {
             CpuOption::EnableWaitStates(_) => self.enable_wait_states,
             CpuOption::TraceLoggingEnabled(_) => self.trace_enabled,
             CpuOption::EnableServiceInterrupt(_) => self.enable_service_interrupt,
+            CpuOption::RepIterationLimit(_) => self.rep_iteration_limit > 0,
+            CpuOption::DecodeOnly(_) => self.decode_only,
+            CpuOption::RecentTraceDepth(_) => self.recent_trace_depth > 0,
+            CpuOption::BusAccessLogDepth(_) => self.bus_log_depth > 0,
         }
     }
 
@@ -605,6 +605,10 @@ impl Intel808x {
             }
         }
 
+        if self.bus_log_depth > 0 {
+            self.record_bus_access();
+        }
+
         self.bus_status = BusStatus::Passive;
         self.address_bus = (self.address_bus & !0xFF) | (self.data_bus as u32);
     }
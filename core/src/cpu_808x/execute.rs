@@ -40,6 +40,7 @@ use crate::{
         OperandType,
         QueueOp,
         Segment,
+        UnhandledOpcode,
         OPCODE_PREFIX_REP1,
         OPCODE_PREFIX_REP2,
         OPCODE_SEG_OVERRIDE_MASK,
@@ -91,6 +92,43 @@ macro_rules! alu_op {
 // rustfmt chokes on large match statements.
 #[rustfmt::skip]
 impl Intel808x {
+    /// Returns true if the instruction can transfer control. Used in decode-only mode.
+    pub(crate) fn is_control_flow(mnemonic: Mnemonic) -> bool {
+        matches!(
+            mnemonic,
+            Mnemonic::JMP
+                | Mnemonic::JMPF
+                | Mnemonic::CALL
+                | Mnemonic::CALLF
+                | Mnemonic::RETN
+                | Mnemonic::RETF
+                | Mnemonic::IRET
+                | Mnemonic::INT
+                | Mnemonic::INT3
+                | Mnemonic::INTO
+                | Mnemonic::JO
+                | Mnemonic::JNO
+                | Mnemonic::JB
+                | Mnemonic::JNB
+                | Mnemonic::JZ
+                | Mnemonic::JNZ
+                | Mnemonic::JBE
+                | Mnemonic::JNBE
+                | Mnemonic::JS
+                | Mnemonic::JNS
+                | Mnemonic::JP
+                | Mnemonic::JNP
+                | Mnemonic::JL
+                | Mnemonic::JNL
+                | Mnemonic::JLE
+                | Mnemonic::JNLE
+                | Mnemonic::JCXZ
+                | Mnemonic::LOOP
+                | Mnemonic::LOOPNE
+                | Mnemonic::LOOPE
+        )
+    }
+
     /// Execute the current instruction. At the phase this function is called we have
     /// fetched and decoded any prefixes, the opcode byte, modrm and any displacement
     /// and populated an Instruction struct.
@@ -104,7 +142,7 @@ impl Intel808x {
     /// requires the next instruction byte to be fetched and is handled by finalize().
     #[rustfmt::skip]
     pub fn execute_instruction(&mut self) -> ExecutionResult {
-        let mut unhandled: bool = false;
+        let mut unhandled: Option<UnhandledOpcode> = None;
         let mut jump: bool = false;
         let mut exception: CpuException = CpuException::NoException;
 
@@ -112,6 +150,16 @@ impl Intel808x {
 
         self.trace_comment("EXECUTE");
         
+        // Tally this instruction if profiling. Don't count subsequent iterations of a REP instruction.
+        if self.profiling && !self.rep_init {
+            self.profile.count(self.i.opcode, self.instruction_address);
+        }
+
+        // Record this instruction in the recent trace if enabled.
+        if self.recent_trace_depth > 0 && !self.rep_init {
+            self.record_recent_trace();
+        }
+
         // TODO: Check optimization here. We could reset several flags at once if they were in a
         //       bitfield.
         // Reset instruction reentrancy flag
@@ -140,6 +188,28 @@ impl Intel808x {
             self.cycle();
         }
 
+        // In decode-only mode, record the instruction and skip over it. load_operand() has already
+        // skipped the EA operand load, so a skipped instruction makes no memory or IO access.
+        // Control flow instructions are still executed, so that the trace follows the path the
+        // program would take given the current register and flag state. These have their normal
+        // side effects: CALL and INT push a return address and flags to the stack, RET and IRET
+        // pop them, and LOOP decrements CX.
+        if self.decode_only {
+            self.record_decode_trace();
+
+            if !Intel808x::is_control_flow(self.i.mnemonic) {
+                // Skip the instruction the way a jump would, so that any code fetch in progress
+                // completes before the queue is flushed.
+                self.biu_fetch_suspend();
+                cycles!(self, 2);
+                self.pc = self.instruction_ip.wrapping_add(self.i.size as u16);
+                self.cycle();
+                self.biu_queue_flush();
+                self.cycle();
+                return ExecutionResult::OkayJump;
+            }
+        }
+
         // Set the microcode PC for this opcode.
         self.mc_pc = MICROCODE_ADDRESS_8088[self.i.opcode as usize];
 
@@ -664,20 +734,21 @@ impl Intel808x {
                 // CALLF - Call Far addr16:16
                 // This instruction reads a direct FAR address from the instruction queue. (See 0xEA for its twin JMPF)
                 let (segment, offset) = self.read_operand_faraddr();
+                let (ret_cs, ret_ip) = (self.cs, self.ip());
                 self.farcall(segment, offset, true);
 
                 // Save next address if we step over this CALL.
-                self.step_over_target = Some(CpuAddress::Segmented(self.cs, self.ip()));
+                self.step_over_target = Some(CpuAddress::Segmented(ret_cs, ret_ip));
 
                 self.push_call_stack(
                     CallStackEntry::CallF {
-                        ret_cs: self.cs,
-                        ret_ip: self.ip(),
+                        ret_cs,
+                        ret_ip,
                         call_cs: segment,
                         call_ip: offset
                     },
-                    self.cs,
-                    self.ip(),
+                    ret_cs,
+                    ret_ip,
                 );
 
                 /*
@@ -1434,18 +1505,16 @@ impl Intel808x {
                     self.set_breakpoint_flag();
                 }
             }
-            0xF0 => {
-                unhandled = true;
-            }
-            0xF1 => {
-                // Does nothing?
-                self.cycle();
+            0xF0 | 0xF1 => {
+                // LOCK prefix. 0xF1 is an undocumented alias of LOCK on the 8088. Both are consumed as
+                // prefixes by decode(), so we never execute them as opcodes.
+                unhandled = Some(UnhandledOpcode::Invalid);
             }
             0xF2 => {
-                unhandled = true;
+                unhandled = Some(UnhandledOpcode::Invalid);
             }
             0xF3 => {
-                unhandled = true;
+                unhandled = Some(UnhandledOpcode::Invalid);
             }
             0xF4 => {
                 // HLT - Halt
@@ -1599,7 +1668,7 @@ impl Intel808x {
                             }
                         }
                     }                                 
-                    _=> unhandled = true
+                    _=> unhandled = Some(UnhandledOpcode::Unimplemented)
                 }
             }
             0xF7 => {
@@ -1724,7 +1793,7 @@ impl Intel808x {
                             }
                         }                        
                     }
-                    _=> unhandled = true
+                    _=> unhandled = Some(UnhandledOpcode::Unimplemented)
                 }
             }
             0xF8 => {
@@ -1917,7 +1986,7 @@ impl Intel808x {
                         self.push_u8(op_value, ReadWriteFlag::RNI);
                     }                                                           
                     _ => {
-                        unhandled = true;
+                        unhandled = Some(UnhandledOpcode::Unimplemented);
                     }
                 }
             }
@@ -1994,21 +2063,22 @@ impl Intel808x {
                             self.cycle_i(0x068);
                             let (segment, offset) = self.read_operand_farptr(self.i.operand1_type, self.i.segment_override, ReadWriteFlag::Normal).unwrap();
                             let next_i = self.ip();
+                            let ret_cs = self.cs;
             
                             self.farcall(segment, offset, true);
 
                             // Save next address if we step over this CALL.
-                            self.step_over_target = Some(CpuAddress::Segmented(self.cs, next_i));
+                            self.step_over_target = Some(CpuAddress::Segmented(ret_cs, next_i));
 
                             // Add to call stack
                             self.push_call_stack(
                                 CallStackEntry::CallF {
-                                    ret_cs: self.cs,
+                                    ret_cs,
                                     ret_ip: next_i,
                                     call_cs: segment,
                                     call_ip: offset
                                 },
-                                self.cs,
+                                ret_cs,
                                 next_i
                             );
                         }
@@ -2103,28 +2173,50 @@ impl Intel808x {
                         self.push_u16(op_value, ReadWriteFlag::RNI);
                     }                    
                     _=> {
-                        unhandled = true;
+                        unhandled = Some(UnhandledOpcode::Unimplemented);
                     }
                 }
             }
         }
 
+        // Spend any additional cycles configured for this opcode once the instruction completes.
+        if !self.in_rep {
+            if let Some(extra) = self.cycle_adjustments.as_ref().map(|table| table[self.i.opcode as usize]) {
+                if extra > 0 {
+                    self.cycles(extra as u32);
+                }
+            }
+        }
+
         // Reset REP init flag. This flag is set after a rep-prefixed instruction is executed for the first time. It
         // should be preserved between executions of a rep-prefixed instruction unless an interrupt occurs, in which
         // case the rep-prefix instruction terminates normally after RPTI. This flag determines whether RPTS is
         // run when executing the instruction.
         if !self.in_rep {
             self.rep_init = false;
+            self.rep_iterations = 0;
         }
         else {
             self.instruction_reentrant = true;
         }
 
-        if unhandled {
-            // This won't happen - the 8088 has no concept of an invalid instruction and we have implemented
-            // all opcodes.
-            unreachable!("Invalid opcode!");
-            //ExecutionResult::UnsupportedOpcode(self.i.opcode)
+        if let Some(kind) = unhandled {
+            // This shouldn't happen - the 8088 has no concept of an invalid instruction and we have implemented
+            // all opcodes. An Invalid opcode means decode() passed us a prefix, and an Unimplemented one is a
+            // missing implementation. Report enough context to diagnose either rather than panicking.
+            let bytes: Vec<String> = self.instruction_bytes().iter().map(|b| format!("{:02X}", b)).collect();
+            ExecutionResult::UnhandledOpcode(
+                kind,
+                self.i.opcode,
+                format!(
+                    "{} at [{:04X}:{:04X}] bytes: [{}] prefixes: {:08X}",
+                    self.i,
+                    self.cs,
+                    self.instruction_ip,
+                    bytes.join(" "),
+                    self.i.prefixes
+                ),
+            )
         }
         else if self.halted && !self.reported_halt && !self.get_flag(Flag::Interrupt) && !self.get_flag(Flag::Trap) {
             // CPU was halted with interrupts disabled - will not continue
@@ -2145,7 +2237,12 @@ impl Intel808x {
                 // Set step-over target so that we can skip long REP instructions.
                 // Normally the step behavior during REP is to perform a single iteration.
                 self.step_over_target = Some(CpuAddress::Segmented(self.cs, self.ip()));
-                ExecutionResult::OkayRep
+
+                // Guard against a REP instruction that never terminates.
+                match self.rep_iteration_guard() {
+                    Some(err) => ExecutionResult::ExecutionError(err),
+                    None => ExecutionResult::OkayRep,
+                }
             }
         }
         else {
@@ -2157,3 +2254,101 @@ impl Intel808x {
         }
     }
 }
+
+#[cfg(test)]
+impl Intel808x {
+    /// Execute a pre-decoded instruction directly, bypassing instruction fetch and decode. This
+    /// isolates the execution unit for testing and profiling of individual opcodes.
+    ///
+    /// The caller is responsible for providing a consistent Instruction:
+    /// - `opcode` and `decode_idx` must select the same entry in the decode table.
+    /// - Operand types and sizes must be valid for the opcode, as decode would produce them.
+    /// - `size` must match the encoded length, and `instruction_ip` must point at the instruction,
+    ///   as execute uses both to advance IP and compute relative targets.
+    ///
+    /// Any memory operand is loaded before execution, as it would be during a normal step.
+    pub(crate) fn execute_decoded(&mut self, i: Instruction) -> ExecutionResult {
+        self.i = i;
+        self.load_operand();
+        self.execute_instruction()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu_common::{operands::OperandSize, Instruction};
+
+    #[test]
+    fn test_execute_decoded_add() {
+        let mut cpu = Intel808x::default();
+        // Use the 8088's 4-byte queue, as the builder would.
+        cpu.queue.set_size(4, 1);
+        cpu.set_register16(Register16::AX, 0x7FFF);
+        cpu.set_register16(Register16::BX, 0x0001);
+
+        // ADD AX, BX (01 D8)
+        let i = Instruction {
+            decode_idx: 0x01,
+            opcode: 0x01,
+            size: 2,
+            mnemonic: Mnemonic::ADD,
+            operand1_type: OperandType::Register16(Register16::AX),
+            operand1_size: OperandSize::Operand16,
+            operand2_type: OperandType::Register16(Register16::BX),
+            operand2_size: OperandSize::Operand16,
+            ..Default::default()
+        };
+
+        assert!(matches!(cpu.execute_decoded(i), ExecutionResult::Okay));
+        assert_eq!(cpu.get_register16(Register16::AX), 0x8000);
+        assert_eq!(cpu.get_register16(Register16::BX), 0x0001);
+        assert!(cpu.get_flag(Flag::Overflow));
+        assert!(cpu.get_flag(Flag::Sign));
+        assert!(cpu.get_flag(Flag::AuxCarry));
+        assert!(!cpu.get_flag(Flag::Carry));
+        assert!(!cpu.get_flag(Flag::Zero));
+    }
+
+    #[test]
+    fn test_execute_decoded_unhandled() {
+        let mut cpu = Intel808x::default();
+        cpu.queue.set_size(4, 1);
+        cpu.cs = 0x1000;
+        cpu.instruction_ip = 0x0100;
+
+        // 0xF1 is a LOCK prefix alias, which decode consumes. It is not an opcode.
+        let i = Instruction {
+            decode_idx: 0xF1,
+            opcode: 0xF1,
+            size: 1,
+            mnemonic: Mnemonic::LOCK,
+            ..Default::default()
+        };
+        match cpu.execute_decoded(i) {
+            ExecutionResult::UnhandledOpcode(UnhandledOpcode::Invalid, 0xF1, context) => {
+                assert!(context.starts_with("lock at [1000:0100]"), "context: {}", context);
+                assert!(context.contains("prefixes: 00000000"), "context: {}", context);
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // 0xFE is a valid opcode, but group 4 has no implementation for NOP.
+        let i = Instruction {
+            decode_idx: 0xFE,
+            opcode: 0xFE,
+            size: 2,
+            mnemonic: Mnemonic::NOP,
+            operand1_type: OperandType::Register8(Register8::AL),
+            operand1_size: OperandSize::Operand8,
+            ..Default::default()
+        };
+        match cpu.execute_decoded(i) {
+            ExecutionResult::UnhandledOpcode(UnhandledOpcode::Unimplemented, 0xFE, context) => {
+                assert!(context.starts_with("nop al at [1000:0100]"), "context: {}", context);
+                assert!(context.contains("bytes: ["), "context: {}", context);
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+}
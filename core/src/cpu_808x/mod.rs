@@ -37,6 +37,8 @@ use crate::cpu_common::{
     instruction::Instruction,
     AddressingMode,
     CpuAddress,
+    CpuProfile,
+    CpuResetState,
     CpuStringState,
     CpuSubType,
     ExecutionResult,
@@ -81,7 +83,7 @@ use crate::{
     bus::{BusInterface, MEM_BPA_BIT, MEM_BPE_BIT, MEM_RET_BIT, MEM_SW_BIT},
     bytequeue::*,
     cpu_808x::{microcode::*, queue::InstructionQueue},
-    cpu_common::{CpuOption, CpuType, TraceMode},
+    cpu_common::{BusAccessLogEntry, BusAccessType, CpuOption, CpuType, InstructionHook, TraceMode, DECODE_TRACE_LEN},
     cycles_mc,
     syntax_token::*,
     tracelogger::TraceLogger,
@@ -138,6 +140,10 @@ const FETCH_DELAY: u8 = 2;
 const CPU_HISTORY_LEN: usize = 32;
 const CPU_CALL_STACK_LEN: usize = 48;
 
+// Default maximum number of iterations of a single REP-prefixed instruction before we assume
+// something has gone wrong. CX can only request 65535 iterations, so this is never hit normally.
+const REP_ITERATION_LIMIT_DEFAULT: u32 = 0x20000;
+
 const INTERRUPT_VEC_LEN: usize = 4;
 const INTERRUPT_BREAKPOINT: u8 = 1;
 
@@ -422,6 +428,24 @@ pub enum HistoryEntry {
     },
 }
 
+/// Maximum number of instruction bytes kept per recent trace entry. This covers the longest 8088
+/// instruction without prefixes.
+const RECENT_TRACE_BYTES: usize = 6;
+/// Upper bound on the space reserved up front for the recent trace. Deeper traces grow on demand.
+const RECENT_TRACE_RESERVE_MAX: usize = 4096;
+
+/// An entry in the recent instruction trace, kept for dumping after a CPU error. Entries are fixed
+/// size so that recording one does not allocate; they are only formatted when the trace is dumped.
+#[derive(Copy, Clone)]
+pub struct RecentTraceEntry {
+    cs: u16,
+    ip: u16,
+    opcode: u8,
+    mnemonic: Mnemonic,
+    size: u8,
+    bytes: [u8; RECENT_TRACE_BYTES],
+}
+
 #[derive(Copy, Clone)]
 pub struct InterruptDescriptor {
     itype: InterruptType,
@@ -591,6 +615,7 @@ pub struct Intel808x {
     step_over_breakpoint: Option<u32>,
 
     reset_vector: CpuAddress,
+    reset_state: CpuResetState,
     reset_queue:  Option<Vec<u8>>,
 
     enable_service_interrupt: bool,
@@ -606,6 +631,19 @@ pub struct Intel808x {
     off_rails_detection: bool,
     opcode0_counter: u32,
 
+    profiling: bool,
+    profile: CpuProfile,
+    rep_iteration_limit: u32,
+    rep_iterations: u32,
+    decode_only: bool,
+    decode_trace: VecDeque<Instruction>,
+    recent_trace_depth: usize,
+    recent_trace: VecDeque<RecentTraceEntry>,
+    cycle_adjustments: Option<Box<[u8; 256]>>,
+    instruction_hook: Option<InstructionHook>,
+    bus_log_depth: usize,
+    bus_log: VecDeque<BusAccessLogEntry>,
+
     rng: Option<rand::rngs::StdRng>,
 
     #[cfg(feature = "cpu_validator")]
@@ -862,6 +900,7 @@ impl Intel808x {
         cpu.instruction_history = VecDeque::with_capacity(16);
 
         cpu.reset_vector = CpuAddress::Segmented(0xFFFF, 0x0000);
+        cpu.rep_iteration_limit = REP_ITERATION_LIMIT_DEFAULT;
 
         cpu.int_flags = vec![0; 256];
         cpu.io_flags = vec![0; 0x10000];
@@ -928,6 +967,14 @@ impl Intel808x {
             panic!("Invalid CpuAddress for reset vector.");
         }
 
+        // Seed any registers specified by the configured reset state
+        for (reg, value) in self.reset_state.registers() {
+            self.set_register16(reg, value);
+        }
+        if let Some(flags) = self.reset_state.flags {
+            self.set_flags(flags);
+        }
+
         self.address_latch = 0;
         self.bus_status = BusStatus::Passive;
         self.bus_status_latch = BusStatus::Passive;
@@ -953,11 +1000,14 @@ impl Intel808x {
         self.reported_halt = false;
         self.halt_not_hold = false;
         self.opcode0_counter = 0;
+        self.rep_iterations = 0;
         self.interrupt_inhibit = false;
         self.intr_pending = false;
         self.in_int = false;
         self.is_error = false;
         self.instruction_history.clear();
+        self.recent_trace.clear();
+        self.bus_log.clear();
         self.call_stack.clear();
         //self.int_flags = vec![0; 256];
         //self.io_flags = vec![0; 0x10000];
@@ -1023,6 +1073,73 @@ impl Intel808x {
         self.instruction_count
     }
 
+    /// Enable or disable execution profiling. Enabling profiling clears any previously collected profile.
+    pub fn enable_profiling(&mut self, state: bool) {
+        if state && !self.profiling {
+            self.profile.clear();
+        }
+        self.profiling = state;
+    }
+
+    pub fn get_profile(&self) -> &CpuProfile {
+        &self.profile
+    }
+
+    /// Return the instructions recorded in decode-only mode, oldest first, clearing the trace.
+    /// Only the last DECODE_TRACE_LEN instructions are retained.
+    pub fn take_decode_trace(&mut self) -> Vec<Instruction> {
+        self.decode_trace.drain(..).collect()
+    }
+
+    /// Record an instruction in the decode-only trace, discarding the oldest entry if full.
+    fn record_decode_trace(&mut self) {
+        if self.decode_trace.len() >= DECODE_TRACE_LEN {
+            self.decode_trace.pop_front();
+        }
+        self.decode_trace.push_back(self.i.clone());
+    }
+
+    /// Return the entries in the bus access log, oldest first, clearing the log.
+    pub fn take_bus_access_log(&mut self) -> Vec<BusAccessLogEntry> {
+        self.bus_log.drain(..).collect()
+    }
+
+    /// Record the memory bus transfer that just completed in the bus access log, evicting the
+    /// oldest entry if the log is full.
+    pub fn record_bus_access(&mut self) {
+        let access_type = match self.bus_status_latch {
+            BusStatus::CodeFetch => BusAccessType::CodeFetch,
+            BusStatus::MemRead => BusAccessType::Read,
+            BusStatus::MemWrite => BusAccessType::Write,
+            _ => return,
+        };
+
+        while self.bus_log.len() >= self.bus_log_depth {
+            self.bus_log.pop_front();
+        }
+
+        let value = match self.transfer_size {
+            TransferSize::Byte => self.data_bus & 0x00FF,
+            TransferSize::Word => self.data_bus,
+        };
+
+        self.bus_log.push_back(BusAccessLogEntry {
+            address: self.address_latch,
+            value,
+            access_type,
+            cs: self.cs,
+            ip: self.instruction_ip,
+            cycle: self.cycle_num,
+        });
+    }
+
+    /// Install a table of additional cycles to spend on each instruction, indexed by opcode.
+    /// This allows approximating the timing of clone CPUs that are slower than an 8088 on
+    /// specific instructions. Pass None to restore stock 8088 timings.
+    pub fn set_cycle_adjustments(&mut self, table: Option<Box<[u8; 256]>>) {
+        self.cycle_adjustments = table;
+    }
+
     /// Calculate the value of IP as needed. The IP register on the 808X is not a physical register,
     /// but produced on demand by adjusting PC by the size of the queue.
     #[inline]
@@ -1441,6 +1558,15 @@ impl Intel808x {
         self.reset_vector = reset_vector;
     }
 
+    /// Set the register state to apply on reset. If CS or IP are specified, they replace the
+    /// corresponding part of the reset vector.
+    pub fn set_reset_state(&mut self, state: CpuResetState) {
+        if let CpuAddress::Segmented(segment, offset) = self.reset_vector {
+            self.reset_vector = CpuAddress::Segmented(state.cs.unwrap_or(segment), state.ip.unwrap_or(offset));
+        }
+        self.reset_state = state;
+    }
+
     pub fn get_reset_vector(&self) -> CpuAddress {
         self.reset_vector
     }
@@ -1668,6 +1794,10 @@ impl Intel808x {
     /// than to mark the return address as the end of that CALL/INT and rewind when we reach that
     /// address again. It isn't perfect, but "good enough" for debugging.
     pub fn rewind_call_stack(&mut self, addr: u32) {
+        if self.call_stack.is_empty() {
+            // Nothing to unwind. This can happen if we returned through an address flagged before a reset.
+            return;
+        }
         let mut return_addr: u32 = 0;
 
         let pos = self.call_stack.iter().position(|&call| {
@@ -1713,6 +1843,9 @@ impl Intel808x {
             log::warn!("resume() called but not halted!");
         }
         self.halted = false;
+        self.halt_not_hold = false;
+        // Allow a subsequent HLT with interrupts disabled to be reported again.
+        self.reported_halt = false;
     }
 
     /// Set the status of the CPU's INTR line.
@@ -1905,6 +2038,67 @@ impl Intel808x {
         disassembly_string
     }
 
+    /// Record the current instruction in the recent instruction trace, evicting the oldest entry
+    /// if the trace is full.
+    pub fn record_recent_trace(&mut self) {
+        while self.recent_trace.len() >= self.recent_trace_depth {
+            self.recent_trace.pop_front();
+        }
+
+        let mut bytes = [0; RECENT_TRACE_BYTES];
+        for (n, byte) in bytes.iter_mut().take(self.i.size as usize).enumerate() {
+            *byte = self
+                .bus
+                .peek_u8((self.instruction_address as usize + n) & 0xFFFFF)
+                .unwrap_or(0xFF);
+        }
+        self.recent_trace.push_back(RecentTraceEntry {
+            cs: self.cs,
+            ip: self.instruction_ip,
+            opcode: self.i.opcode,
+            mnemonic: self.i.mnemonic,
+            size: self.i.size as u8,
+            bytes,
+        });
+    }
+
+    /// Return the bytes of the current instruction, including prefixes, read from memory.
+    pub fn instruction_bytes(&self) -> Vec<u8> {
+        (0..self.i.size as usize)
+            .map(|n| {
+                self.bus
+                    .peek_u8((self.instruction_address as usize + n) & 0xFFFFF)
+                    .unwrap_or(0xFF)
+            })
+            .collect()
+    }
+
+    /// Dump the recent instruction trace, oldest instruction first.
+    pub fn dump_recent_trace(&self) -> String {
+        let mut trace_string = String::new();
+
+        for entry in &self.recent_trace {
+            let size = entry.size as usize;
+            let mut bytes: Vec<String> = entry.bytes[..size.min(RECENT_TRACE_BYTES)]
+                .iter()
+                .map(|b| format!("{:02X}", b))
+                .collect();
+            if size > RECENT_TRACE_BYTES {
+                // Heavily prefixed instruction, only the first bytes were kept.
+                bytes.push("..".to_string());
+            }
+            trace_string.push_str(&format!(
+                "[{:04X}:{:04X}] {:<20} {} (opcode {:02X})\n",
+                entry.cs,
+                entry.ip,
+                bytes.join(" "),
+                entry.mnemonic.to_string().to_lowercase(),
+                entry.opcode
+            ));
+        }
+        trace_string
+    }
+
     pub fn dump_instruction_history_tokens(&self) -> Vec<Vec<SyntaxToken>> {
         let mut history_vec = Vec::new();
 
@@ -1968,6 +2162,30 @@ impl Intel808x {
         history_vec
     }
 
+    /// Return the call stack as a list of (from_cs, from_ip, to_cs, to_ip) tuples, outermost call first.
+    /// The 'from' address is the return address of the call, the 'to' address is the call target.
+    pub fn get_call_stack(&self) -> Vec<(u16, u16, u16, u16)> {
+        self.call_stack
+            .iter()
+            .map(|call| match *call {
+                CallStackEntry::Call { ret_cs, ret_ip, call_ip } => (ret_cs, ret_ip, ret_cs, call_ip),
+                CallStackEntry::CallF {
+                    ret_cs,
+                    ret_ip,
+                    call_cs,
+                    call_ip,
+                } => (ret_cs, ret_ip, call_cs, call_ip),
+                CallStackEntry::Interrupt {
+                    ret_cs,
+                    ret_ip,
+                    call_cs,
+                    call_ip,
+                    ..
+                } => (ret_cs, ret_ip, call_cs, call_ip),
+            })
+            .collect()
+    }
+
     pub fn dump_call_stack(&self) -> String {
         let mut call_stack_string = String::new();
 
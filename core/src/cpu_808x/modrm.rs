@@ -418,18 +418,15 @@ impl ModRmByte {
             _ => unreachable!("impossible Register16"),
         }
     }
-    // Interpret the 'REG' field as a 16 bit segment register selector
+    // Interpret the 'REG' field as a 16 bit segment register selector.
+    // The 8088 only decodes the low two bits, so encodings 4-7 alias ES, CS, SS and DS.
     pub fn get_op2_segmentreg16(&self) -> Register16 {
-        match self.b_reg {
+        match self.b_reg & 0x03 {
             0x00 => Register16::ES,
             0x01 => Register16::CS,
             0x02 => Register16::SS,
             0x03 => Register16::DS,
-            0x04 => Register16::ES,
-            0x05 => Register16::CS,
-            0x06 => Register16::SS,
-            0x07 => Register16::DS,
-            _ => Register16::InvalidRegister,
+            _ => unreachable!("impossible segment Register16"),
         }
     }
     // Interpret the 'REG' field as a 3 bit opcode extension
@@ -32,7 +32,16 @@
 
 use crate::{
     cpu_808x::{decode::DECODE, *},
-    cpu_common::{CpuAddress, CpuError, CpuException, Disassembly, ExecutionResult, StepResult},
+    cpu_common::{
+        CpuAddress,
+        CpuError,
+        CpuException,
+        Disassembly,
+        ExecutionResult,
+        HookAction,
+        StepResult,
+        UnhandledOpcode,
+    },
     gdr,
 };
 
@@ -45,6 +54,10 @@ impl Intel808x {
     ///
     /// REP string instructions are handled by stopping them after one iteration so that interrupts can
     /// be checked.
+    ///
+    /// Returns the number of cycles elapsed since the previous call to step_finish(). This includes the
+    /// terminating fetch and any interrupt processing performed by step_finish(), so summing the values
+    /// returned by step() accounts for every CPU cycle.
     pub fn step(&mut self, skip_breakpoint: bool) -> Result<(StepResult, u32), CpuError> {
         self.instr_cycle = 0;
         self.instr_elapsed = self.int_elapsed;
@@ -130,6 +143,22 @@ impl Intel808x {
                 }
             }
 
+            // Run the instruction hook, if one is installed. The hook is taken out of the CPU while it
+            // runs so that it can be handed a mutable reference to the CPU.
+            if let Some(mut hook) = self.instruction_hook.take() {
+                let (cs, ip) = (self.cs, self.instruction_ip);
+                let opcode = self.bus.peek_u8(instruction_address as usize).unwrap_or(0xFF);
+                let action = hook(self, cs, ip, opcode);
+                self.instruction_hook = Some(hook);
+
+                // Like an execute breakpoint, a break request is ignored when resuming from a breakpoint.
+                if action == HookAction::Break && !skip_breakpoint {
+                    log::debug!("Instruction hook requested break at {:05X}", instruction_address);
+                    self.set_breakpoint_flag();
+                    return Ok((StepResult::BreakpointHit, 0));
+                }
+            }
+
             // Check if this address is a return from a CALL or INT
             if self.bus.get_flags(instruction_address as usize) & MEM_RET_BIT != 0 {
                 // This address is a return address, rewind the stack
@@ -251,6 +280,23 @@ impl Intel808x {
                 Err(CpuError::UnhandledInstructionError(o, instruction_address))
             }
             */
+            ExecutionResult::UnhandledOpcode(kind, o, context) => {
+                // The 8088 has no invalid opcodes, so this indicates a decode or implementation bug.
+                self.is_running = false;
+                self.is_error = true;
+                match kind {
+                    UnhandledOpcode::Invalid => Err(CpuError::InvalidInstructionError(
+                        *o,
+                        instruction_address,
+                        context.to_string(),
+                    )),
+                    UnhandledOpcode::Unimplemented => Err(CpuError::UnhandledInstructionError(
+                        *o,
+                        instruction_address,
+                        context.to_string(),
+                    )),
+                }
+            }
             ExecutionResult::ExecutionError(e) => {
                 // Something unexpected happened!
                 self.is_running = false;
@@ -340,4 +340,57 @@ impl Intel808x {
         self.rep_end();
         // Flush was on RNI so no extra cycle here
     }
+
+    /// Count an iteration of a REP-prefixed instruction. If the configured iteration limit is exceeded,
+    /// terminate the REP and return a description of the runaway instruction. A limit of 0 disables the
+    /// guard.
+    pub fn rep_iteration_guard(&mut self) -> Option<String> {
+        self.rep_iterations = self.rep_iterations.wrapping_add(1);
+
+        if self.rep_iteration_limit > 0 && self.rep_iterations > self.rep_iteration_limit {
+            let err = format!(
+                "REP iteration limit ({}) exceeded: {:?} at [{:04X}:{:04X}] with CX={:04X}",
+                self.rep_iteration_limit,
+                self.rep_mnemonic,
+                self.cs,
+                self.instruction_ip,
+                self.c.x()
+            );
+            self.rep_end();
+            self.rep_iterations = 0;
+            return Some(err);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rep_iteration_guard() {
+        let mut cpu = Intel808x::default();
+        cpu.rep_iteration_limit = 16;
+        cpu.in_rep = true;
+        cpu.rep_mnemonic = Mnemonic::MOVSB;
+        cpu.set_register16(Register16::CX, 0xFFFF);
+
+        // Simulate a REP that never decrements CX.
+        for _ in 0..16 {
+            assert!(cpu.rep_iteration_guard().is_none());
+        }
+
+        let err = cpu.rep_iteration_guard().expect("REP guard should fire");
+        assert!(err.contains("MOVSB"));
+        assert!(err.contains("CX=FFFF"));
+        assert!(!cpu.in_rep);
+
+        // A limit of 0 disables the guard.
+        cpu.rep_iteration_limit = 0;
+        cpu.in_rep = true;
+        for _ in 0..64 {
+            assert!(cpu.rep_iteration_guard().is_none());
+        }
+    }
 }
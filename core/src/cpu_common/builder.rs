@@ -31,7 +31,7 @@
 */
 use crate::{
     cpu_808x::Intel808x,
-    cpu_common::{CpuDispatch, CpuSubType, CpuType, TraceMode},
+    cpu_common::{CpuDispatch, CpuSubType, CpuType, CycleAdjustments, TraceMode},
     cpu_validator::{ValidatorMode, ValidatorType},
     cpu_vx0::NecVx0,
     tracelogger::TraceLogger,
@@ -44,6 +44,7 @@ pub struct CpuBuilder {
     cpu_subtype: Option<CpuSubType>,
     trace_mode: TraceMode,
     trace_logger: Option<TraceLogger>,
+    cycle_adjustments: CycleAdjustments,
     #[cfg(feature = "cpu_validator")]
     validator_type: ValidatorType,
     #[cfg(feature = "cpu_validator")]
@@ -79,6 +80,7 @@ impl CpuBuilder {
                         #[cfg(feature = "cpu_validator")]
                         self.validator_baud.take().unwrap_or_default(),
                     );
+                    cpu.set_cycle_adjustments(self.cycle_adjustments.table(cpu_type));
                     return Ok(cpu.into());
                 }
                 CpuType::NecV20 => {
@@ -95,6 +97,7 @@ impl CpuBuilder {
                         #[cfg(feature = "cpu_validator")]
                         self.validator_baud.take().unwrap_or_default(),
                     );
+                    cpu.set_cycle_adjustments(self.cycle_adjustments.table(cpu_type));
                     return Ok(cpu.into());
                 }
                 _ => {
@@ -127,6 +130,12 @@ impl CpuBuilder {
         self
     }
 
+    /// Provide per-CPU-type cycle adjustment tables. Only the table for the CPU type being built is used.
+    pub fn with_cycle_adjustments(mut self, cycle_adjustments: CycleAdjustments) -> Self {
+        self.cycle_adjustments = cycle_adjustments;
+        self
+    }
+
     #[cfg(feature = "cpu_validator")]
     pub fn with_validator_type(mut self, validator_type: ValidatorType) -> Self {
         self.validator_type = validator_type;
@@ -35,8 +35,8 @@ use std::{error::Error, fmt, fmt::Display};
 
 #[derive(Debug)]
 pub enum CpuError {
-    InvalidInstructionError(u8, u32),
-    UnhandledInstructionError(u8, u32),
+    InvalidInstructionError(u8, u32, String),
+    UnhandledInstructionError(u8, u32, String),
     InstructionDecodeError(u32),
     ExecutionError(u32, String),
     CpuHaltedError(u32),
@@ -46,15 +46,15 @@ impl Error for CpuError {}
 impl Display for CpuError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match &*self {
-            CpuError::InvalidInstructionError(o, addr) => write!(
+            CpuError::InvalidInstructionError(o, addr, context) => write!(
                 f,
-                "An invalid instruction was encountered: {:02X} at address: {:06X}",
-                o, addr
+                "An invalid instruction was encountered: {:02X} at address: {:06X} {}",
+                o, addr, context
             ),
-            CpuError::UnhandledInstructionError(o, addr) => write!(
+            CpuError::UnhandledInstructionError(o, addr, context) => write!(
                 f,
-                "An unhandled instruction was encountered: {:02X} at address: {:06X}",
-                o, addr
+                "An unhandled instruction was encountered: {:02X} at address: {:06X} {}",
+                o, addr, context
             ),
             CpuError::InstructionDecodeError(addr) => write!(
                 f,
@@ -42,6 +42,7 @@ pub mod operands;
 pub mod services;
 
 use enum_dispatch::enum_dispatch;
+use fxhash::FxHashMap;
 use serde::Deserialize;
 use std::str::FromStr;
 
@@ -81,6 +82,9 @@ pub const OPCODE_PREFIX_REPMASK: u32 = 0b1111_0000_0000;
 // The first two bits of the prefixes field stores the number of prefixes to restore from 0-3.
 pub const OPCODE_PREFIX_CT_MASK: u32 = 0b0000_0000_0011;
 
+/// Maximum number of instructions retained in the decode-only trace. Older entries are discarded.
+pub const DECODE_TRACE_LEN: usize = 1024;
+
 #[derive(Debug, Default, PartialEq)]
 pub enum ExecutionResult {
     #[default]
@@ -88,11 +92,21 @@ pub enum ExecutionResult {
     OkayJump,
     OkayRep,
     //UnsupportedOpcode(u8),        // All opcodes implemented.
+    UnhandledOpcode(UnhandledOpcode, u8, String),
     ExecutionError(String),
     ExceptionError(CpuException),
     Halt,
 }
 
+/// The reason the execution unit could not handle an opcode.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum UnhandledOpcode {
+    /// The byte is not an opcode at all, such as a prefix that decode should have consumed.
+    Invalid,
+    /// The opcode is valid, but has no implementation.
+    Unimplemented,
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum CpuException {
     NoException,
@@ -130,6 +144,49 @@ pub enum Register16 {
     InvalidRegister,
 }
 
+/// Register state to apply on CPU reset, on top of the default reset state. This allows a machine
+/// to specify a reset vector and initial register values other than those of the IBM PC.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct CpuResetState {
+    pub cs: Option<u16>,
+    pub ip: Option<u16>,
+    pub flags: Option<u16>,
+    pub ax: Option<u16>,
+    pub bx: Option<u16>,
+    pub cx: Option<u16>,
+    pub dx: Option<u16>,
+    pub sp: Option<u16>,
+    pub bp: Option<u16>,
+    pub si: Option<u16>,
+    pub di: Option<u16>,
+    pub ds: Option<u16>,
+    pub es: Option<u16>,
+    pub ss: Option<u16>,
+}
+
+impl CpuResetState {
+    /// Return the general and segment register values to seed, excluding CS and IP which are
+    /// set via the reset vector.
+    pub fn registers(&self) -> Vec<(Register16, u16)> {
+        [
+            (Register16::AX, self.ax),
+            (Register16::BX, self.bx),
+            (Register16::CX, self.cx),
+            (Register16::DX, self.dx),
+            (Register16::SP, self.sp),
+            (Register16::BP, self.bp),
+            (Register16::SI, self.si),
+            (Register16::DI, self.di),
+            (Register16::DS, self.ds),
+            (Register16::ES, self.es),
+            (Register16::SS, self.ss),
+        ]
+        .into_iter()
+        .filter_map(|(reg, val)| val.map(|v| (reg, v)))
+        .collect()
+    }
+}
+
 #[derive(Copy, Clone, Default, Debug)]
 pub enum Segment {
     None,
@@ -282,8 +339,46 @@ pub enum CpuOption {
     EnableWaitStates(bool),
     TraceLoggingEnabled(bool),
     EnableServiceInterrupt(bool),
+    RepIterationLimit(u32),
+    DecodeOnly(bool),
+    RecentTraceDepth(u32),
+    BusAccessLogDepth(u32),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BusAccessType {
+    CodeFetch,
+    Read,
+    Write,
 }
 
+/// An entry in the bus access log, recorded for each memory bus transfer.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BusAccessLogEntry {
+    pub address: u32,
+    pub value: u16,
+    pub access_type: BusAccessType,
+    /// CS:IP of the instruction executing when the access occurred. For code fetches, this is
+    /// the instruction that was executing when the byte was prefetched.
+    pub cs: u16,
+    pub ip: u16,
+    pub cycle: u64,
+}
+
+/// The action requested by an instruction hook.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum HookAction {
+    /// Execute the instruction normally.
+    Continue,
+    /// Stop before executing the instruction, as if an execute breakpoint was hit.
+    Break,
+}
+
+/// A callback invoked before each instruction is fetched, with the CPU, the CS:IP of the
+/// instruction and its first byte. The hook may inspect or modify CPU state before the
+/// instruction runs. Changing CS or IP from a hook is not supported.
+pub type InstructionHook = Box<dyn FnMut(&mut dyn Cpu, u16, u16, u8) -> HookAction>;
+
 #[derive(Debug)]
 pub enum StepResult {
     Normal,
@@ -315,6 +410,72 @@ pub enum QueueOp {
     Subsequent,
 }
 
+/// Tables of additional cycles to spend on each instruction, indexed by opcode. A separate table
+/// may be provided for each CPU type; the table for the CPU being built is selected by CpuBuilder.
+#[derive(Clone, Debug, Default)]
+pub struct CycleAdjustments {
+    tables: FxHashMap<CpuType, Box<[u8; 256]>>,
+}
+
+impl CycleAdjustments {
+    pub fn set_table(&mut self, cpu_type: CpuType, table: Box<[u8; 256]>) {
+        self.tables.insert(cpu_type, table);
+    }
+
+    pub fn table(&self, cpu_type: CpuType) -> Option<Box<[u8; 256]>> {
+        self.tables.get(&cpu_type).cloned()
+    }
+}
+
+/// Execution counts collected while CPU profiling is enabled.
+/// Each instruction is counted once, regardless of how many iterations a REP prefix causes.
+#[derive(Clone, Debug)]
+pub struct CpuProfile {
+    /// Executions of each opcode, indexed by opcode byte.
+    pub opcodes: [u64; 256],
+    /// Executions of each instruction, keyed by flat address.
+    pub addresses: FxHashMap<u32, u64>,
+}
+
+impl Default for CpuProfile {
+    fn default() -> Self {
+        Self {
+            opcodes: [0; 256],
+            addresses: FxHashMap::default(),
+        }
+    }
+}
+
+impl CpuProfile {
+    #[inline]
+    pub fn count(&mut self, opcode: u8, address: u32) {
+        self.opcodes[opcode as usize] += 1;
+        *self.addresses.entry(address).or_insert(0) += 1;
+    }
+
+    pub fn clear(&mut self) {
+        *self = CpuProfile::default();
+    }
+
+    pub fn total(&self) -> u64 {
+        self.opcodes.iter().sum()
+    }
+
+    /// Return up to 'n' (opcode, count) pairs, most frequently executed first.
+    pub fn hot_opcodes(&self, n: usize) -> Vec<(u8, u64)> {
+        let mut hot: Vec<(u8, u64)> = self
+            .opcodes
+            .iter()
+            .enumerate()
+            .filter(|(_, ct)| **ct > 0)
+            .map(|(op, ct)| (op as u8, *ct))
+            .collect();
+        hot.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        hot.truncate(n);
+        hot
+    }
+}
+
 pub fn calc_linear_address(segment: u16, offset: u16) -> u32 {
     (((segment as u32) << 4) + offset as u32) & 0xFFFFFu32
 }
@@ -388,6 +549,7 @@ pub trait Cpu {
     // General CPU control
     fn reset(&mut self);
     fn set_reset_vector(&mut self, address: CpuAddress);
+    fn set_reset_state(&mut self, state: CpuResetState);
     fn set_reset_queue_contents(&mut self, contents: Vec<u8>);
     fn set_end_address(&mut self, address: CpuAddress);
     fn set_nmi(&mut self, state: bool);
@@ -413,6 +575,8 @@ pub trait Cpu {
     fn dump_instruction_history_string(&self) -> String;
     fn dump_instruction_history_tokens(&self) -> Vec<Vec<SyntaxToken>>;
     fn dump_call_stack(&self) -> String;
+    fn dump_recent_trace(&self) -> String;
+    fn get_call_stack(&self) -> Vec<(u16, u16, u16, u16)>;
     fn get_service_event(&mut self) -> Option<ServiceEvent>;
     fn get_cycle_states(&self) -> &Vec<CycleState>;
     fn get_cycle_trace(&self) -> &Vec<String>;
@@ -420,12 +584,20 @@ pub trait Cpu {
 
     fn get_string_state(&self) -> CpuStringState;
 
+    // Profiling
+    fn enable_profiling(&mut self, state: bool);
+    fn get_profile(&self) -> &CpuProfile;
+    fn take_decode_trace(&mut self) -> Vec<Instruction>;
+    fn take_bus_access_log(&mut self) -> Vec<BusAccessLogEntry>;
+    fn set_cycle_adjustments(&mut self, table: Option<Box<[u8; 256]>>);
+
     // Eval
     fn eval_address(&self, expr: &str) -> Option<CpuAddress>;
 
     // Breakpoints
     fn clear_breakpoint_flag(&mut self);
     fn set_breakpoints(&mut self, bp_list: Vec<BreakPointType>);
+    fn set_instruction_hook(&mut self, hook: Option<InstructionHook>);
     fn get_step_over_breakpoint(&self) -> Option<CpuAddress>;
     fn set_step_over_breakpoint(&mut self, address: CpuAddress);
     fn get_sw_data(&self) -> Vec<StopWatchData>;
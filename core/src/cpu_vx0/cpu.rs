@@ -36,14 +36,18 @@ use crate::{
     bytequeue::ByteQueue,
     cpu_common::{
         instruction::Instruction,
+        BusAccessLogEntry,
         Cpu,
         CpuAddress,
         CpuDispatch,
         CpuError,
         CpuOption,
+        CpuProfile,
+        CpuResetState,
         CpuStringState,
         CpuType,
         Disassembly,
+        InstructionHook,
         QueueOp,
         Register8,
         ServiceEvent,
@@ -129,6 +133,14 @@ impl Cpu for NecVx0 {
             panic!("Invalid CpuAddress for reset vector.");
         }
 
+        // Seed any registers specified by the configured reset state
+        for (reg, value) in self.reset_state.registers() {
+            self.set_register16(reg, value);
+        }
+        if let Some(flags) = self.reset_state.flags {
+            self.set_flags(flags);
+        }
+
         self.address_latch = 0;
         self.bus_status = BusStatus::Passive;
         self.bus_status_latch = BusStatus::Passive;
@@ -224,6 +236,11 @@ impl Cpu for NecVx0 {
         self.set_reset_vector(address);
     }
 
+    #[inline]
+    fn set_reset_state(&mut self, state: CpuResetState) {
+        self.set_reset_state(state);
+    }
+
     #[inline]
     fn set_end_address(&mut self, address: CpuAddress) {
         let end_addr;
@@ -321,6 +338,28 @@ impl Cpu for NecVx0 {
         self.get_instruction_ct()
     }
 
+    #[inline]
+    fn enable_profiling(&mut self, state: bool) {
+        self.enable_profiling(state);
+    }
+
+    #[inline]
+    fn get_profile(&self) -> &CpuProfile {
+        self.get_profile()
+    }
+
+    fn take_decode_trace(&mut self) -> Vec<Instruction> {
+        Vec::new()
+    }
+
+    fn take_bus_access_log(&mut self) -> Vec<BusAccessLogEntry> {
+        Vec::new()
+    }
+
+    fn set_cycle_adjustments(&mut self, table: Option<Box<[u8; 256]>>) {
+        self.set_cycle_adjustments(table)
+    }
+
     /// Return the resolved flat address of CS:CORR(PC)
     #[inline]
     fn flat_ip(&self) -> u32 {
@@ -352,6 +391,14 @@ impl Cpu for NecVx0 {
         self.dump_call_stack()
     }
 
+    fn dump_recent_trace(&self) -> String {
+        String::new()
+    }
+
+    fn get_call_stack(&self) -> Vec<(u16, u16, u16, u16)> {
+        self.get_call_stack()
+    }
+
     #[inline]
     fn get_service_event(&mut self) -> Option<ServiceEvent> {
         self.service_events.pop_front()
@@ -395,6 +442,10 @@ impl Cpu for NecVx0 {
         self.set_breakpoints(bp_list)
     }
 
+    fn set_instruction_hook(&mut self, _hook: Option<InstructionHook>) {
+        log::debug!("Instruction hooks are not supported on this CPU type.");
+    }
+
     #[inline]
     fn get_step_over_breakpoint(&self) -> Option<CpuAddress> {
         self.get_step_over_breakpoint()
@@ -472,6 +523,18 @@ impl Cpu for NecVx0 {
                 log::debug!("Setting EnableServiceInterrupt to: {:?}", state);
                 self.enable_service_interrupt = state;
             }
+            CpuOption::RepIterationLimit(_) => {
+                log::debug!("RepIterationLimit is not supported on this CPU type.");
+            }
+            CpuOption::DecodeOnly(_) => {
+                log::debug!("DecodeOnly is not supported on this CPU type.");
+            }
+            CpuOption::RecentTraceDepth(_) => {
+                log::debug!("RecentTraceDepth is not supported on this CPU type.");
+            }
+            CpuOption::BusAccessLogDepth(_) => {
+                log::debug!("BusAccessLogDepth is not supported on this CPU type.");
+            }
         }
     }
 
@@ -486,6 +549,10 @@ impl Cpu for NecVx0 {
             CpuOption::EnableWaitStates(_) => self.enable_wait_states,
             CpuOption::TraceLoggingEnabled(_) => self.trace_enabled,
             CpuOption::EnableServiceInterrupt(_) => self.enable_service_interrupt,
+            CpuOption::RepIterationLimit(_) => false,
+            CpuOption::DecodeOnly(_) => false,
+            CpuOption::RecentTraceDepth(_) => false,
+            CpuOption::BusAccessLogDepth(_) => false,
         }
     }
 
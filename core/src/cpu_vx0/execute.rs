@@ -39,6 +39,7 @@ use crate::{
         OperandType,
         QueueOp,
         Segment,
+        UnhandledOpcode,
         OPCODE_PREFIX_REP1,
         OPCODE_PREFIX_REP2,
         OPCODE_PREFIX_REP3,
@@ -106,7 +107,7 @@ impl NecVx0 {
     /// requires the next instruction byte to be fetched and is handled by finalize().
     #[rustfmt::skip]
     pub fn execute_instruction(&mut self) -> ExecutionResult {
-        let mut unhandled: bool = false;
+        let mut unhandled: Option<UnhandledOpcode> = None;
         let mut jump: bool = false;
         let mut exception: CpuException = CpuException::NoException;
 
@@ -114,6 +115,11 @@ impl NecVx0 {
 
         self.trace_comment("EXECUTE");
         
+        // Tally this instruction if profiling. Don't count subsequent iterations of a REP instruction.
+        if self.profiling && !self.rep_init {
+            self.profile.count(self.i.opcode, self.instruction_address);
+        }
+
         // TODO: Check optimization here. We could reset several flags at once if they were in a
         //       bitfield.
         // Reset instruction reentrancy flag
@@ -532,7 +538,7 @@ impl NecVx0 {
                 }
             }
             0x6C..=0x6F => {
-                unhandled = true;
+                unhandled = Some(UnhandledOpcode::Unimplemented);
             }
             0x70..=0x7F => {
                 // JMP rel8 variants
@@ -755,20 +761,21 @@ impl NecVx0 {
                 // CALLF - Call Far addr16:16
                 // This instruction reads a direct FAR address from the instruction queue. (See 0xEA for its twin JMPF)
                 let (segment, offset) = self.read_operand_faraddr();
+                let (ret_cs, ret_ip) = (self.cs, self.ip());
                 self.farcall(segment, offset, true);
 
                 // Save next address if we step over this CALL.
-                self.step_over_target = Some(CpuAddress::Segmented(self.cs, self.ip()));
+                self.step_over_target = Some(CpuAddress::Segmented(ret_cs, ret_ip));
 
                 self.push_call_stack(
                     CallStackEntry::CallF {
-                        ret_cs: self.cs,
-                        ret_ip: self.ip(),
+                        ret_cs,
+                        ret_ip,
                         call_cs: segment,
                         call_ip: offset
                     },
-                    self.cs,
-                    self.ip(),
+                    ret_cs,
+                    ret_ip,
                 );
 
                 /*
@@ -1586,18 +1593,16 @@ impl NecVx0 {
                 self.biu_io_write_u8(op1_value + 1, (op2_value >> 8 & 0xFF) as u8, ReadWriteFlag::RNI);
                 */
             }
-            0xF0 => {
-                unhandled = true;
-            }
-            0xF1 => {
-                // Does nothing?
-                self.cycle();
+            0xF0 | 0xF1 => {
+                // LOCK prefix. 0xF1 is an undocumented alias of LOCK. Both are consumed as prefixes by
+                // decode(), so we never execute them as opcodes.
+                unhandled = Some(UnhandledOpcode::Invalid);
             }
             0xF2 => {
-                unhandled = true;
+                unhandled = Some(UnhandledOpcode::Invalid);
             }
             0xF3 => {
-                unhandled = true;
+                unhandled = Some(UnhandledOpcode::Invalid);
             }
             0xF4 => {
                 // HLT - Halt
@@ -1746,7 +1751,7 @@ impl NecVx0 {
                             }
                         }
                     }                                 
-                    _=> unhandled = true
+                    _=> unhandled = Some(UnhandledOpcode::Unimplemented)
                 }
             }
             0xF7 => {
@@ -1866,7 +1871,7 @@ impl NecVx0 {
                             }
                         }                        
                     }
-                    _=> unhandled = true
+                    _=> unhandled = Some(UnhandledOpcode::Unimplemented)
                 }
             }
             0xF8 => {
@@ -2058,7 +2063,7 @@ impl NecVx0 {
                         self.push_u8(op_value, ReadWriteFlag::RNI);
                     }                                                           
                     _ => {
-                        unhandled = true;
+                        unhandled = Some(UnhandledOpcode::Unimplemented);
                     }
                 }
             }
@@ -2133,21 +2138,22 @@ impl NecVx0 {
                             self.cycle_i(0x068);
                             let (segment, offset) = self.read_operand_farptr(self.i.operand1_type, self.i.segment_override, ReadWriteFlag::Normal).unwrap();
                             let next_i = self.ip();
+                            let ret_cs = self.cs;
             
                             self.farcall(segment, offset, true);
 
                             // Save next address if we step over this CALL.
-                            self.step_over_target = Some(CpuAddress::Segmented(self.cs, next_i));
+                            self.step_over_target = Some(CpuAddress::Segmented(ret_cs, next_i));
 
                             // Add to call stack
                             self.push_call_stack(
                                 CallStackEntry::CallF {
-                                    ret_cs: self.cs,
+                                    ret_cs,
                                     ret_ip: next_i,
                                     call_cs: segment,
                                     call_ip: offset
                                 },
-                                self.cs,
+                                ret_cs,
                                 next_i
                             );
                         }
@@ -2238,12 +2244,21 @@ impl NecVx0 {
                         self.push_u16(op_value, ReadWriteFlag::RNI);
                     }                    
                     _=> {
-                        unhandled = true;
+                        unhandled = Some(UnhandledOpcode::Unimplemented);
                     }
                 }
             }
         }
 
+        // Spend any additional cycles configured for this opcode once the instruction completes.
+        if !self.in_rep {
+            if let Some(extra) = self.cycle_adjustments.as_ref().map(|table| table[self.i.opcode as usize]) {
+                if extra > 0 {
+                    self.cycles(extra as u32);
+                }
+            }
+        }
+
         // Reset REP init flag. This flag is set after a rep-prefixed instruction is executed for the first time. It
         // should be preserved between executions of a rep-prefixed instruction unless an interrupt occurs, in which
         // case the rep-prefix instruction terminates normally after RPTI. This flag determines whether RPTS is
@@ -2255,9 +2270,22 @@ impl NecVx0 {
             self.instruction_reentrant = true;
         }
 
-        if unhandled {
-            unreachable!("Invalid opcode!");
-            //ExecutionResult::UnsupportedOpcode(self.i.opcode)
+        if let Some(kind) = unhandled {
+            // An Invalid opcode means decode() passed us a prefix, and an Unimplemented one is a missing
+            // implementation. Report enough context to diagnose either rather than panicking.
+            let bytes: Vec<String> = self.instruction_bytes().iter().map(|b| format!("{:02X}", b)).collect();
+            ExecutionResult::UnhandledOpcode(
+                kind,
+                self.i.opcode,
+                format!(
+                    "{} at [{:04X}:{:04X}] bytes: [{}] prefixes: {:08X}",
+                    self.i,
+                    self.cs,
+                    self.instruction_ip,
+                    bytes.join(" "),
+                    self.i.prefixes
+                ),
+            )
         }
         else if self.halted && !self.reported_halt && !self.get_flag(Flag::Interrupt) && !self.get_flag(Flag::Trap) {
             // CPU was halted with interrupts disabled - will not continue
@@ -2289,6 +2317,68 @@ impl NecVx0 {
             }
         }
     }
+}
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu_common::{operands::OperandSize, Instruction};
+
+    #[test]
+    fn test_execute_unhandled() {
+        let mut cpu = NecVx0::default();
+        // Use the V20's 4-byte queue, as the builder would.
+        cpu.queue.set_size(4, 1);
+        cpu.cs = 0x1000;
+        cpu.instruction_ip = 0x0100;
+
+        // 0xF2 is a REPNE prefix, which decode consumes. It is not an opcode.
+        cpu.i = Instruction {
+            decode_idx: 0xF2,
+            opcode: 0xF2,
+            size: 1,
+            mnemonic: Mnemonic::REPNE,
+            ..Default::default()
+        };
+        match cpu.execute_instruction() {
+            ExecutionResult::UnhandledOpcode(UnhandledOpcode::Invalid, 0xF2, context) => {
+                assert!(context.starts_with("repne at [1000:0100]"), "context: {}", context);
+                assert!(context.contains("prefixes: 00000000"), "context: {}", context);
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // 0xF1 is a LOCK prefix alias, which decode also consumes.
+        cpu.i = Instruction {
+            decode_idx: 0xF1,
+            opcode: 0xF1,
+            size: 1,
+            mnemonic: Mnemonic::LOCK,
+            ..Default::default()
+        };
+        match cpu.execute_instruction() {
+            ExecutionResult::UnhandledOpcode(UnhandledOpcode::Invalid, 0xF1, context) => {
+                assert!(context.starts_with("lock at [1000:0100]"), "context: {}", context);
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
 
+        // 0xFE is a valid opcode, but group 4 has no implementation for NOP.
+        cpu.i = Instruction {
+            decode_idx: 0xFE,
+            opcode: 0xFE,
+            size: 2,
+            mnemonic: Mnemonic::NOP,
+            operand1_type: OperandType::Register8(Register8::AL),
+            operand1_size: OperandSize::Operand8,
+            ..Default::default()
+        };
+        match cpu.execute_instruction() {
+            ExecutionResult::UnhandledOpcode(UnhandledOpcode::Unimplemented, 0xFE, context) => {
+                assert!(context.starts_with("nop al at [1000:0100]"), "context: {}", context);
+                assert!(context.contains("bytes: ["), "context: {}", context);
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
 }
@@ -75,6 +75,8 @@ use crate::{
         instruction::Instruction,
         CpuAddress,
         CpuOption,
+        CpuProfile,
+        CpuResetState,
         CpuStringState,
         CpuSubType,
         CpuType,
@@ -589,6 +591,7 @@ pub struct NecVx0 {
     step_over_breakpoint: Option<u32>,
 
     reset_vector: CpuAddress,
+    reset_state: CpuResetState,
     reset_queue:  Option<Vec<u8>>,
 
     enable_service_interrupt: bool,
@@ -604,6 +607,10 @@ pub struct NecVx0 {
     off_rails_detection: bool,
     opcode0_counter: u32,
 
+    profiling: bool,
+    profile: CpuProfile,
+    cycle_adjustments: Option<Box<[u8; 256]>>,
+
     rng: Option<rand::rngs::StdRng>,
 
     #[cfg(feature = "cpu_validator")]
@@ -859,6 +866,24 @@ impl NecVx0 {
         self.instruction_count
     }
 
+    /// Enable or disable execution profiling. Enabling profiling clears any previously collected profile.
+    pub fn enable_profiling(&mut self, state: bool) {
+        if state && !self.profiling {
+            self.profile.clear();
+        }
+        self.profiling = state;
+    }
+
+    pub fn get_profile(&self) -> &CpuProfile {
+        &self.profile
+    }
+
+    /// Install a table of additional cycles to spend on each instruction, indexed by opcode.
+    /// Pass None to restore stock V20 timings.
+    pub fn set_cycle_adjustments(&mut self, table: Option<Box<[u8; 256]>>) {
+        self.cycle_adjustments = table;
+    }
+
     /// Calculate the value of IP as needed. The IP register on the 808X is not a physical register,
     /// but produced on demand by adjusting PC by the size of the queue.
     #[inline]
@@ -1277,6 +1302,15 @@ impl NecVx0 {
         self.reset_vector = reset_vector;
     }
 
+    /// Set the register state to apply on reset. If CS or IP are specified, they replace the
+    /// corresponding part of the reset vector.
+    pub fn set_reset_state(&mut self, state: CpuResetState) {
+        if let CpuAddress::Segmented(segment, offset) = self.reset_vector {
+            self.reset_vector = CpuAddress::Segmented(state.cs.unwrap_or(segment), state.ip.unwrap_or(offset));
+        }
+        self.reset_state = state;
+    }
+
     pub fn get_reset_vector(&self) -> CpuAddress {
         self.reset_vector
     }
@@ -1504,6 +1538,10 @@ impl NecVx0 {
     /// than to mark the return address as the end of that CALL/INT and rewind when we reach that
     /// address again. It isn't perfect, but "good enough" for debugging.
     pub fn rewind_call_stack(&mut self, addr: u32) {
+        if self.call_stack.is_empty() {
+            // Nothing to unwind. This can happen if we returned through an address flagged before a reset.
+            return;
+        }
         let mut return_addr: u32 = 0;
 
         let pos = self.call_stack.iter().position(|&call| {
@@ -1549,6 +1587,9 @@ impl NecVx0 {
             log::warn!("resume() called but not halted!");
         }
         self.halted = false;
+        self.halt_not_hold = false;
+        // Allow a subsequent HLT with interrupts disabled to be reported again.
+        self.reported_halt = false;
     }
 
     /// Set the status of the CPU's INTR line.
@@ -1797,6 +1838,41 @@ impl NecVx0 {
         history_vec
     }
 
+    /// Return the call stack as a list of (from_cs, from_ip, to_cs, to_ip) tuples, outermost call first.
+    /// The 'from' address is the return address of the call, the 'to' address is the call target.
+    pub fn get_call_stack(&self) -> Vec<(u16, u16, u16, u16)> {
+        self.call_stack
+            .iter()
+            .map(|call| match *call {
+                CallStackEntry::Call { ret_cs, ret_ip, call_ip } => (ret_cs, ret_ip, ret_cs, call_ip),
+                CallStackEntry::CallF {
+                    ret_cs,
+                    ret_ip,
+                    call_cs,
+                    call_ip,
+                } => (ret_cs, ret_ip, call_cs, call_ip),
+                CallStackEntry::Interrupt {
+                    ret_cs,
+                    ret_ip,
+                    call_cs,
+                    call_ip,
+                    ..
+                } => (ret_cs, ret_ip, call_cs, call_ip),
+            })
+            .collect()
+    }
+
+    /// Return the bytes of the current instruction, including prefixes, read from memory.
+    pub fn instruction_bytes(&self) -> Vec<u8> {
+        (0..self.i.size as usize)
+            .map(|n| {
+                self.bus
+                    .peek_u8((self.instruction_address as usize + n) & 0xFFFFF)
+                    .unwrap_or(0xFF)
+            })
+            .collect()
+    }
+
     pub fn dump_call_stack(&self) -> String {
         let mut call_stack_string = String::new();
 
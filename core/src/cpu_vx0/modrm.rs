@@ -448,16 +448,12 @@ impl ModRmByte {
     }
     // Interpret the 'REG' field as a 16 bit segment register selector
     pub fn get_op2_segmentreg16(&self) -> Register16 {
-        match self.b_reg {
+        match self.b_reg & 0x03 {
             0x00 => Register16::ES,
             0x01 => Register16::CS,
             0x02 => Register16::SS,
             0x03 => Register16::DS,
-            0x04 => Register16::ES,
-            0x05 => Register16::CS,
-            0x06 => Register16::SS,
-            0x07 => Register16::DS,
-            _ => Register16::InvalidRegister,
+            _ => unreachable!("impossible segment Register16"),
         }
     }
     // Interpret the 'REG' field as a 3 bit opcode extension
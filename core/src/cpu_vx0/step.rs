@@ -31,7 +31,15 @@
 */
 
 use crate::{
-    cpu_common::{CpuError, CpuException, Disassembly, ExecutionResult, StepResult, OPCODE_PREFIX_0F},
+    cpu_common::{
+        CpuError,
+        CpuException,
+        Disassembly,
+        ExecutionResult,
+        StepResult,
+        UnhandledOpcode,
+        OPCODE_PREFIX_0F,
+    },
     cpu_vx0::{decode::DECODE, *},
     vgdr,
 };
@@ -256,6 +264,23 @@ impl NecVx0 {
                 Err(CpuError::UnhandledInstructionError(o, instruction_address))
             }
             */
+            ExecutionResult::UnhandledOpcode(kind, o, context) => {
+                // A prefix reached execute or an opcode is not implemented yet. Stop the CPU and report it.
+                self.is_running = false;
+                self.is_error = true;
+                match kind {
+                    UnhandledOpcode::Invalid => Err(CpuError::InvalidInstructionError(
+                        *o,
+                        instruction_address,
+                        context.to_string(),
+                    )),
+                    UnhandledOpcode::Unimplemented => Err(CpuError::UnhandledInstructionError(
+                        *o,
+                        instruction_address,
+                        context.to_string(),
+                    )),
+                }
+            }
             ExecutionResult::ExecutionError(e) => {
                 // Something unexpected happened!
                 self.is_running = false;
@@ -180,6 +180,7 @@ pub struct AttributeController {
     last_den: bool,
     shift_reg: u128,
     shift_buf: [u8; 8],
+    last_pixel: u8,
 }
 
 impl Default for AttributeController {
@@ -200,6 +201,7 @@ impl Default for AttributeController {
             last_den: false,
             shift_reg: 0,
             shift_buf: [0; 8],
+            last_pixel: 0,
         }
     }
 }
@@ -311,6 +313,25 @@ impl AttributeController {
         self.mode_control.display_type()
     }
 
+    /// Return the two color outputs selected by the Video Status Mux field of the Color Plane
+    /// Enable register, in bits 4 & 5 as they appear in Input Status Register 1.
+    ///
+    /// IBM:
+    /// 00: Red, Blue
+    /// 01: Secondary Blue, Green
+    /// 10: Secondary Red, Secondary Green
+    /// 11: Not used
+    pub fn video_status_bits(&self) -> u8 {
+        // Color outputs are in the order B, G, R, sB, sG, sR starting from bit 0.
+        let (bit5, bit4) = match self.color_plane_enable.video_status_mux() {
+            0b00 => (2, 0),
+            0b01 => (3, 1),
+            0b10 => (5, 4),
+            _ => return 0,
+        };
+        ((self.last_pixel >> bit5) & 0x01) << 5 | ((self.last_pixel >> bit4) & 0x01) << 4
+    }
+
     /// Load the attribute controller with a new AttributeInput.
     /// Should be called after shift_outX to make room for the new character clock worth of data.
     pub fn load(&mut self, input: AttributeInput, clock_select: ClockSelect, den: bool) {
@@ -383,6 +404,7 @@ impl AttributeController {
 
     pub fn shift_out64(&mut self) -> u64 {
         let out_data = ((self.shift_reg << ((self.pel_panning & 0x07) * 8)) >> 64) as u64;
+        self.last_pixel = out_data as u8;
 
         // Shift the attribute data 64 bits to make room for next character clock
         self.shift_reg <<= 64;
@@ -396,6 +418,7 @@ impl AttributeController {
         let mut out_data1 = 0;
 
         let out_data = ((self.shift_reg << (std::cmp::min(self.pel_panning, 0x07) * 8)) >> 64) as u64;
+        self.last_pixel = out_data as u8;
 
         // Shift the attribute data 64 bits to make room for next character clock
         self.shift_reg <<= 64;
@@ -48,6 +48,7 @@ pub enum GraphicsRegister {
 
 #[allow(dead_code)]
 #[bitfield]
+#[derive(Copy, Clone)]
 pub struct GDataRotateRegister {
     pub count: B3,
     #[bits = 2]
@@ -65,10 +66,10 @@ pub struct GModeRegister {
     #[bits = 1]
     pub read_mode: ReadMode,
     pub odd_even: OddEvenModeComplement,
-    #[bits = 1]
+    #[bits = 2]
     pub shift_mode: ShiftMode,
     #[skip]
-    unused: B2,
+    unused: B1,
 }
 
 #[bitfield]
@@ -81,6 +82,14 @@ pub struct GMiscellaneousRegister {
     pub unused: B4,
 }
 
+/// The data the Graphics Controller presents to the Attribute Controller for one character clock.
+pub enum GraphicsOutput<'a> {
+    /// Serialized pixel data, in graphics mode.
+    Serial(&'a [u8]),
+    /// An unpacked glyph row and attribute byte, in alphanumeric mode.
+    Parallel(&'a [u8], u8),
+}
+
 #[derive(Copy, Clone, Debug, BitfieldSpecifier)]
 pub enum OddEvenModeComplement {
     Sequential,
@@ -117,10 +126,16 @@ pub enum ReadMode {
     ReadComparedPlanes,
 }
 
+/// Shift Register mode, bits 5-6 of the Mode register.
+///
+/// The IBM EGA only implements bit 5. Bit 6 selects the interleaved 8-bit mode used by later
+/// EGA-compatible and VGA serializers, and takes precedence over bit 5 when both are set.
 #[derive(Copy, Clone, Debug, BitfieldSpecifier)]
 pub enum ShiftMode {
     Standard,
     CGACompatible,
+    EightBits,
+    Reserved,
 }
 
 pub struct GraphicsController {
@@ -159,8 +174,10 @@ impl Default for GraphicsController {
             graphics_read_map_select: 0,
             graphics_mode: GModeRegister::new(),
             graphics_micellaneous: GMiscellaneousRegister::new(),
-            graphics_color_dont_care: 0,
-            graphics_bitmask: 0,
+            // Compare all planes and pass all CPU bits through, so that read mode 1 and the
+            // write modes behave sensibly before the BIOS programs these registers.
+            graphics_color_dont_care: 0x0F,
+            graphics_bitmask: 0xFF,
 
             latches: [0; 4],
 
@@ -176,6 +193,12 @@ impl GraphicsController {
         GraphicsController::default()
     }
 
+    /// Return all registers, the register select pointer and the data latches to their
+    /// power-on state.
+    pub fn reset(&mut self) {
+        *self = GraphicsController::default();
+    }
+
     /// Handle a write to one of the Graphics Position Registers.
     ///
     /// According to IBM documentation, both these registers should be set to
@@ -265,6 +288,35 @@ impl GraphicsController {
         }
     }
 
+    /// Return the value of the currently selected Graphics register.
+    ///
+    /// The Graphics registers are write-only on the IBM EGA, so this is not exposed via IO reads.
+    /// It is intended for debug display, and for EGA-compatible chips that allow readback.
+    pub fn read_graphics_data(&self) -> u8 {
+        match self.graphics_register_selected {
+            GraphicsRegister::SetReset => self.graphics_set_reset,
+            GraphicsRegister::EnableSetReset => self.graphics_enable_set_reset,
+            GraphicsRegister::ColorCompare => self.graphics_color_compare,
+            GraphicsRegister::DataRotate => self.graphics_data_rotate.into_bytes()[0] & 0x1F,
+            GraphicsRegister::ReadMapSelect => self.graphics_read_map_select,
+            GraphicsRegister::Mode => self.graphics_mode.into_bytes()[0] & 0x3F,
+            GraphicsRegister::Miscellaneous => self.graphics_micellaneous.into_bytes()[0] & 0x0F,
+            GraphicsRegister::ColorDontCare => self.graphics_color_dont_care,
+            GraphicsRegister::BitMask => self.graphics_bitmask,
+        }
+    }
+
+    /// Return the current contents of the four plane latches.
+    pub fn get_latches(&self) -> [u8; 4] {
+        self.latches
+    }
+
+    /// Set the four plane latches directly. This is intended for debugging only - the latches are
+    /// normally loaded by CPU reads.
+    pub fn set_latches(&mut self, latches: [u8; 4]) {
+        self.latches = latches;
+    }
+
     /// Implement the serializer output of the Graphics Controller, for graphics modes.
     /// Unlike CPU reads, this does not set the latches, however it performs address manipulation
     /// and allows for processing such as CGA compatibility shifting.
@@ -285,6 +337,15 @@ impl GraphicsController {
             //&TEST_SEQUENCE
             &self.serialize_buf
         }
+        else if let ShiftMode::EightBits | ShiftMode::Reserved = self.graphics_mode.shift_mode() {
+            // Interleaved mode. Each plane supplies two 4-bit pixels, high nibble first, in plane order
+            for plane in 0..4 {
+                let byte = seq.read_u8(plane, offset, address & 0x01);
+                self.serialize_buf[plane * 2] = byte >> 4;
+                self.serialize_buf[plane * 2 + 1] = byte & 0x0F;
+            }
+            &self.serialize_buf
+        }
         else {
             // Normal EGA mode
             seq.serialize_linear(offset)
@@ -300,6 +361,20 @@ impl GraphicsController {
         (glyph_unpacked, attr)
     }
 
+    /// Fetch the data for one character clock at `address`, honoring the Graphics Mode bit of the
+    /// Miscellaneous register. In graphics mode the display planes are serialized into pixels.
+    /// In alphanumeric mode planes 0 & 1 supply the character and attribute, and the character
+    /// generator latches load the glyph row from plane 2.
+    pub fn fetch<'a>(&'a mut self, seq: &'a Sequencer, address: usize, row: u8) -> GraphicsOutput<'a> {
+        if self.graphics_micellaneous.graphics_mode() {
+            GraphicsOutput::Serial(self.serialize(seq, address))
+        }
+        else {
+            let (glyph, attr) = self.parallel(seq, address, row);
+            GraphicsOutput::Parallel(glyph, attr)
+        }
+    }
+
     /// Implement a read of the Graphics Controller via the CPU. This sets the latches, performs
     /// address manipulation, and executes the pixel pipeline.
     pub fn cpu_read_u8(&mut self, seq: &Sequencer, address: usize, page_select: PageSelect) -> u8 {
@@ -311,9 +386,9 @@ impl GraphicsController {
             }
         };
 
-        /*        if self.graphics_mode.odd_even() {
-            //offset >>= 1;
-        }*/
+        // In odd/even mode the offset is not shifted. The EGA replaces address bit 0 with the page select
+        // bit (see map_address()), and a0 selects the even or odd plane of each pair. Characters are thus
+        // stored at even offsets of plane 0 and attributes at odd offsets of plane 1, as parallel() expects.
 
         // Load all the latches regardless of selected plane
         for i in 0..4 {
@@ -364,11 +439,12 @@ impl GraphicsController {
             }
         };
 
-        if let OddEvenModeComplement::OddEven = self.graphics_mode.odd_even() {
-            //offset >>= 1;
-        }
-
-        seq.read_u8(0, offset, a0)
+        // Select the plane as Read Mode 0 would.
+        let plane = match self.graphics_mode.odd_even() {
+            OddEvenModeComplement::Sequential => self.graphics_read_map_select as usize,
+            OddEvenModeComplement::OddEven => (self.graphics_read_map_select as usize & !0x01) | a0,
+        };
+        seq.read_u8(plane, offset, a0)
     }
 
     pub fn cpu_write_u8(&mut self, seq: &mut Sequencer, address: usize, page_select: PageSelect, byte: u8) {
@@ -606,6 +682,290 @@ impl GraphicsController {
         graphics_vec.push((format!("{:?}", GraphicsRegister::ColorDontCare), VideoCardStateEntry::String(format!("{:04b}", self.graphics_color_dont_care))));
         graphics_vec.push((format!("{:?}", GraphicsRegister::BitMask), VideoCardStateEntry::String(format!("{:08b}", self.graphics_bitmask))));
 
+        for (i, latch) in self.latches.iter().enumerate() {
+            graphics_vec.push((format!("Latch {}", i), VideoCardStateEntry::String(format!("{:02X}", latch))));
+        }
+
         graphics_vec
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup(map_mask: u8) -> (GraphicsController, Sequencer) {
+        let mut gc = GraphicsController::new();
+        let mut seq = Sequencer::new();
+
+        // Sequential addressing, planes enabled by map mask
+        seq.write_address(0x04);
+        seq.write_data(0x04);
+        seq.write_address(0x02);
+        seq.write_data(map_mask);
+
+        // Pass all bits through the bit mask
+        gc.write_graphics_address(0x08);
+        gc.write_graphics_data(0xFF);
+        (gc, seq)
+    }
+
+    #[test]
+    fn test_read_graphics_data() {
+        let mut gc = GraphicsController::new();
+
+        // (register, value written, value read back after masking unused bits)
+        let regs = [
+            (0x00, 0xFA, 0x0A),
+            (0x01, 0xF5, 0x05),
+            (0x02, 0xFC, 0x0C),
+            (0x03, 0xFB, 0x1B),
+            (0x04, 0xFE, 0x02),
+            (0x05, 0xFB, 0x3B),
+            (0x06, 0xFD, 0x0D),
+            (0x07, 0xF3, 0x03),
+            (0x08, 0xA5, 0xA5),
+        ];
+
+        for (reg, write, read) in regs {
+            gc.write_graphics_address(reg);
+            gc.write_graphics_data(write);
+            assert_eq!(gc.read_graphics_data(), read, "graphics register {:02X}", reg);
+        }
+    }
+
+    #[test]
+    fn test_map_mask_mode0() {
+        let (mut gc, mut seq) = setup(0b0101);
+
+        gc.cpu_write_u8(&mut seq, EGA_MEM_ADDRESS, PageSelect::LowPage, 0xA5);
+
+        assert_eq!(seq.vram.read_u8(0, 0), 0xA5);
+        assert_eq!(seq.vram.read_u8(1, 0), 0x00);
+        assert_eq!(seq.vram.read_u8(2, 0), 0xA5);
+        assert_eq!(seq.vram.read_u8(3, 0), 0x00);
+    }
+
+    #[test]
+    fn test_map_mask_mode1_mode2() {
+        let (mut gc, mut seq) = setup(0b1111);
+
+        // Fill all planes at offset 0, then load the latches from it
+        gc.cpu_write_u8(&mut seq, EGA_MEM_ADDRESS, PageSelect::LowPage, 0x3C);
+        gc.cpu_read_u8(&seq, EGA_MEM_ADDRESS, PageSelect::LowPage);
+
+        // Write Mode 1 copies the latches only into the enabled planes
+        seq.write_address(0x02);
+        seq.write_data(0b0011);
+        gc.write_graphics_address(0x05);
+        gc.write_graphics_data(0x01);
+        gc.cpu_write_u8(&mut seq, EGA_MEM_ADDRESS + 1, PageSelect::LowPage, 0x00);
+
+        assert_eq!(seq.vram.read_u8(0, 1), 0x3C);
+        assert_eq!(seq.vram.read_u8(1, 1), 0x3C);
+        assert_eq!(seq.vram.read_u8(2, 1), 0x00);
+        assert_eq!(seq.vram.read_u8(3, 1), 0x00);
+
+        // Write Mode 2 expands the color bits only into the enabled planes
+        seq.write_address(0x02);
+        seq.write_data(0b1010);
+        gc.write_graphics_address(0x05);
+        gc.write_graphics_data(0x02);
+        gc.cpu_write_u8(&mut seq, EGA_MEM_ADDRESS + 2, PageSelect::LowPage, 0x0F);
+
+        assert_eq!(seq.vram.read_u8(0, 2), 0x00);
+        assert_eq!(seq.vram.read_u8(1, 2), 0xFF);
+        assert_eq!(seq.vram.read_u8(2, 2), 0x00);
+        assert_eq!(seq.vram.read_u8(3, 2), 0xFF);
+    }
+
+    #[test]
+    fn test_latches() {
+        let (mut gc, mut seq) = setup(0b1111);
+
+        for plane in 0..4 {
+            seq.plane_set(plane, 0x10, 0, 0x11 * (plane as u8 + 1));
+        }
+        gc.cpu_read_u8(&seq, EGA_MEM_ADDRESS + 0x10, PageSelect::LowPage);
+        assert_eq!(gc.get_latches(), [0x11, 0x22, 0x33, 0x44]);
+
+        // Latches set manually are copied to VRAM by Write Mode 1
+        gc.set_latches([0xA1, 0xB2, 0xC3, 0xD4]);
+        gc.write_graphics_address(0x05);
+        gc.write_graphics_data(0x01);
+        gc.cpu_write_u8(&mut seq, EGA_MEM_ADDRESS + 0x20, PageSelect::LowPage, 0x00);
+
+        assert_eq!(seq.vram.read_u8(0, 0x20), 0xA1);
+        assert_eq!(seq.vram.read_u8(1, 0x20), 0xB2);
+        assert_eq!(seq.vram.read_u8(2, 0x20), 0xC3);
+        assert_eq!(seq.vram.read_u8(3, 0x20), 0xD4);
+    }
+
+    #[test]
+    fn test_plane_dump() {
+        let (mut gc, mut seq) = setup(0b0100);
+
+        let data = [0xDE, 0xAD, 0xBE, 0xEF];
+        for (i, byte) in data.iter().enumerate() {
+            gc.cpu_write_u8(&mut seq, EGA_MEM_ADDRESS + 0x100 + i, PageSelect::LowPage, *byte);
+        }
+
+        let plane = seq.plane_dump(2).unwrap();
+        assert_eq!(plane.len(), EGA_GFX_PLANE_SIZE);
+        assert_eq!(&plane[0x100..0x104], &data);
+        assert!(plane[..0x100].iter().all(|b| *b == 0));
+        assert!(plane[0x104..].iter().all(|b| *b == 0));
+
+        // Other planes were masked off
+        assert!(seq.plane_dump(0).unwrap().iter().all(|b| *b == 0));
+        assert!(seq.plane_dump(4).is_none());
+    }
+
+    #[test]
+    fn test_odd_even_text() {
+        let mut gc = GraphicsController::new();
+        let mut seq = Sequencer::new();
+
+        // Odd/even addressing, all planes enabled
+        seq.write_address(0x02);
+        seq.write_data(0x0F);
+        gc.write_graphics_address(0x05);
+        gc.write_graphics_data(0x10);
+        gc.write_graphics_address(0x06);
+        gc.write_graphics_data(0x0E);
+        gc.write_graphics_address(0x08);
+        gc.write_graphics_data(0xFF);
+
+        // Write a character/attribute pair
+        gc.cpu_write_u8(&mut seq, CGA_MEM_ADDRESS + 0x10, PageSelect::LowPage, 0x41);
+        gc.cpu_write_u8(&mut seq, CGA_MEM_ADDRESS + 0x11, PageSelect::LowPage, 0x1F);
+
+        // The character lands in the even planes, the attribute in the odd planes
+        assert_eq!(seq.vram.read_u8(0, 0x10), 0x41);
+        assert_eq!(seq.vram.read_u8(2, 0x10), 0x41);
+        assert_eq!(seq.vram.read_u8(1, 0x10), 0x00);
+        assert_eq!(seq.vram.read_u8(1, 0x11), 0x1F);
+        assert_eq!(seq.vram.read_u8(3, 0x11), 0x1F);
+        assert_eq!(seq.vram.read_u8(0, 0x11), 0x00);
+
+        // The CPU reads them back from the same addresses
+        assert_eq!(gc.cpu_read_u8(&seq, CGA_MEM_ADDRESS + 0x10, PageSelect::LowPage), 0x41);
+        assert_eq!(gc.cpu_read_u8(&seq, CGA_MEM_ADDRESS + 0x11, PageSelect::LowPage), 0x1F);
+        assert_eq!(gc.cpu_peek_u8(&seq, CGA_MEM_ADDRESS + 0x11, PageSelect::LowPage), 0x1F);
+
+        // And the text mode fetch sees the attribute for the character
+        let (_, attr) = gc.parallel(&seq, 0x10, 0);
+        assert_eq!(attr, 0x1F);
+    }
+
+    #[test]
+    fn test_shift_modes() {
+        let (mut gc, mut seq) = setup(0b1111);
+
+        for addr in 0..2 {
+            seq.plane_set(0, addr, addr & 0x01, 0xE4);
+            seq.plane_set(1, addr, addr & 0x01, 0x1B);
+            seq.plane_set(2, addr, addr & 0x01, 0x5A);
+            seq.plane_set(3, addr, addr & 0x01, 0xC3);
+        }
+
+        // Standard: one bit from each plane per pixel
+        gc.write_graphics_address(0x05);
+        gc.write_graphics_data(0x00);
+        assert_eq!(gc.serialize(&seq, 0), &[9, 13, 1, 6, 6, 1, 14, 10]);
+
+        // CGA compatible: 2bpp pixels from plane 0 at the even address, then plane 1 at the odd address
+        gc.write_graphics_data(0x20);
+        assert_eq!(gc.serialize(&seq, 0), &[3, 2, 1, 0, 0, 1, 2, 3]);
+
+        // Interleaved: two 4bpp pixels from each plane in turn
+        gc.write_graphics_data(0x40);
+        assert_eq!(gc.serialize(&seq, 0), &[14, 4, 1, 11, 5, 10, 12, 3]);
+
+        // Bit 6 takes precedence over bit 5
+        gc.write_graphics_data(0x60);
+        assert_eq!(gc.serialize(&seq, 0), &[14, 4, 1, 11, 5, 10, 12, 3]);
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut gc = GraphicsController::new();
+        let defaults: Vec<u8> = (0..9)
+            .map(|reg| {
+                gc.write_graphics_address(reg);
+                gc.read_graphics_data()
+            })
+            .collect();
+        assert_eq!(defaults[7], 0x0F);
+        assert_eq!(defaults[8], 0xFF);
+
+        for reg in 0..9 {
+            gc.write_graphics_address(reg);
+            gc.write_graphics_data(0x5A);
+        }
+        gc.latches = [0x11, 0x22, 0x33, 0x44];
+
+        gc.reset();
+        assert_eq!(gc.graphics_register_select_byte, 0);
+        assert!(matches!(gc.graphics_register_selected, GraphicsRegister::SetReset));
+        assert_eq!(gc.latches, [0; 4]);
+        for (reg, default) in defaults.iter().enumerate() {
+            gc.write_graphics_address(reg as u8);
+            assert_eq!(gc.read_graphics_data(), *default, "register {}", reg);
+        }
+    }
+
+    #[test]
+    fn test_graphics_mode_fetch() {
+        let (mut gc, mut seq) = setup(0b1111);
+
+        // Character 'A' with attribute 1F, and the first row of its glyph
+        seq.plane_set(0, 0x00, 0, 0x41);
+        seq.plane_set(1, 0x01, 1, 0x1F);
+        seq.plane_set(2, seq.get_glyph_address(0x41, 0, 0), 0, 0x81);
+
+        // Graphics mode serializes the planes into pixels
+        gc.write_graphics_address(0x06);
+        gc.write_graphics_data(0x01);
+        match gc.fetch(&seq, 0x00, 0) {
+            GraphicsOutput::Serial(pixels) => assert_eq!(pixels, &[0, 1, 0, 0, 0, 0, 0, 1]),
+            GraphicsOutput::Parallel(..) => panic!("expected serialized pixels in graphics mode"),
+        }
+
+        // Alphanumeric mode interprets the same memory as a character and attribute
+        gc.write_graphics_address(0x06);
+        gc.write_graphics_data(0x00);
+        match gc.fetch(&seq, 0x00, 0) {
+            GraphicsOutput::Parallel(glyph, attr) => {
+                assert_eq!(glyph, &BYTE_EXTEND_TABLE[0x81]);
+                assert_eq!(attr, 0x1F);
+            }
+            GraphicsOutput::Serial(_) => panic!("expected character data in alphanumeric mode"),
+        }
+    }
+
+    #[test]
+    fn test_memory_map_switch() {
+        let (mut gc, mut seq) = setup(0b1111);
+
+        // Map A0000, 64K
+        gc.write_graphics_address(0x06);
+        gc.write_graphics_data(0x04);
+
+        gc.cpu_write_u8(&mut seq, EGA_MEM_ADDRESS, PageSelect::LowPage, 0x11);
+        gc.cpu_write_u8(&mut seq, CGA_MEM_ADDRESS, PageSelect::LowPage, 0x22);
+        assert_eq!(seq.vram.read_u8(0, 0), 0x11);
+        assert_eq!(gc.cpu_read_u8(&seq, EGA_MEM_ADDRESS, PageSelect::LowPage), 0x11);
+        assert_eq!(gc.cpu_read_u8(&seq, CGA_MEM_ADDRESS, PageSelect::LowPage), 0x00);
+
+        // Map B8000, 32K. Accesses to A0000 are no longer claimed.
+        gc.write_graphics_address(0x06);
+        gc.write_graphics_data(0x0C);
+
+        gc.cpu_write_u8(&mut seq, CGA_MEM_ADDRESS, PageSelect::LowPage, 0x33);
+        gc.cpu_write_u8(&mut seq, EGA_MEM_ADDRESS, PageSelect::LowPage, 0x44);
+        assert_eq!(seq.vram.read_u8(0, 0), 0x33);
+        assert_eq!(gc.cpu_read_u8(&seq, CGA_MEM_ADDRESS, PageSelect::LowPage), 0x33);
+        assert_eq!(gc.cpu_read_u8(&seq, EGA_MEM_ADDRESS, PageSelect::LowPage), 0x00);
+    }
+}
@@ -90,6 +90,13 @@ pub const EGA_MEM_END_128: usize = EGA_MEM_ADDRESS + EGA_MEM_WINDOW_128 - 1;
 // pub const CGA_MEM_SIZE: usize = 16384;
 pub const EGA_TEXT_PLANE_SIZE: usize = 16384;
 pub const EGA_GFX_PLANE_SIZE: usize = 65536;
+pub const EGA_PLANES: usize = 4;
+// Installed video memory. The base EGA board carries 64K; the Graphics Memory Expansion Card
+// brings it to 128K, and the Memory Module Kit to the full 256K.
+pub const EGA_VRAM_SIZE_64K: usize = 0x10000;
+pub const EGA_VRAM_SIZE_128K: usize = 0x20000;
+pub const EGA_VRAM_SIZE_256K: usize = 0x40000;
+pub const DEFAULT_VRAM_SIZE: usize = EGA_VRAM_SIZE_256K;
 
 // For an EGA card connected to an EGA monitor
 // See http://www.minuszerodegrees.net/ibm_ega/ibm_ega_switch_settings.htm
@@ -554,6 +561,7 @@ pub struct EGACard {
     debug_draw: bool,
 
     dip_sw: DipSwitch,
+    vram_size: usize,
 
     ticks_accum: f64,
     clock_mode: ClockingMode,
@@ -686,6 +694,7 @@ impl Default for EGACard {
             debug_draw: true,
 
             dip_sw: DipSwitch::new(DipSwitchSize::Dip4, DEFAULT_DIP_SWITCH).with_invert_bits(false),
+            vram_size: DEFAULT_VRAM_SIZE,
 
             ticks_accum: 0.0,
             clock_mode: ClockingMode::Cycle,
@@ -787,7 +796,13 @@ impl Default for DisplayExtents {
 }*/
 
 impl EGACard {
-    pub fn new(trace_logger: TraceLogger, clock_mode: ClockingMode, video_frame_debug: bool, dip: Option<u8>) -> Self {
+    pub fn new(
+        trace_logger: TraceLogger,
+        clock_mode: ClockingMode,
+        video_frame_debug: bool,
+        dip: Option<u8>,
+        vram_size: Option<usize>,
+    ) -> Self {
         let mut ega = Self::default();
 
         // If a dip was provided, set the dip switches, otherwise leave them default.
@@ -795,6 +810,10 @@ impl EGACard {
             ega.dip_sw.set_physical_state(dip);
         }
 
+        if let Some(vram_size) = vram_size {
+            ega.set_vram_size(vram_size);
+        }
+
         ega.trace_logger = trace_logger;
         ega.debug = video_frame_debug;
         //ega.debug_draw = video_frame_debug;
@@ -820,19 +839,37 @@ impl EGACard {
         }
     }
 
+    /// Set the amount of installed video memory. Each plane receives a quarter of the total, and
+    /// accesses beyond the installed plane size wrap. Unsupported sizes fall back to 256K.
+    pub fn set_vram_size(&mut self, size: usize) {
+        self.vram_size = match size {
+            EGA_VRAM_SIZE_64K | EGA_VRAM_SIZE_128K | EGA_VRAM_SIZE_256K => size,
+            _ => {
+                log::warn!("EGA: Unsupported VRAM size {:X}, using {:X}", size, DEFAULT_VRAM_SIZE);
+                DEFAULT_VRAM_SIZE
+            }
+        };
+        self.sequencer.vram.set_plane_size(self.vram_size / EGA_PLANES);
+    }
+
     /// Reset the EGA card.
     fn reset_private(&mut self) {
         let trace_logger = std::mem::replace(&mut self.trace_logger, TraceLogger::None);
+        let mut gc = std::mem::take(&mut self.gc);
+        gc.reset();
 
         *self = Self {
             debug: self.debug,
             dip_sw: self.dip_sw,
+            vram_size: self.vram_size,
             debug_draw: self.debug_draw,
             clock_mode: self.clock_mode,
             frame: self.frame,
             trace_logger,
+            gc,
             ..Self::default()
         };
+        self.set_vram_size(self.vram_size);
     }
 
     fn get_cursor_span(&self) -> (u8, u8) {
@@ -930,12 +967,8 @@ impl EGACard {
         // Mux Field.
         // The EGA BIOS performs a diagnostic that senses these line transitions after
         // drawing a line of high-intensity white characters to the screen.
-        // Currently, we just fake this whole affair by setting the bits to be on during
-        // the first few scanlines.
-
-        if self.crtc.scanline() < 9 {
-            byte |= 0x30;
-        }
+        // We report the last pixel shifted out of the Attribute Controller.
+        byte |= self.ac.video_status_bits();
 
         byte
     }
@@ -1086,12 +1119,11 @@ impl EGACard {
                         //self.draw_text_mode_hchar14();
                     }
                     AttributeMode::Graphics => {
-                        let ser = self.gc.serialize(&self.sequencer, self.vma);
-                        self.ac.load(
-                            AttributeInput::Serial(ser),
-                            clock_select,
-                            self.crtc.status.den | self.crtc.in_skew(),
-                        );
+                        let input = match self.gc.fetch(&self.sequencer, self.vma, self.crtc.vlc()) {
+                            GraphicsOutput::Serial(ser) => AttributeInput::Serial(ser),
+                            GraphicsOutput::Parallel(glyph, attr) => AttributeInput::Parallel(glyph, attr, false),
+                        };
+                        self.ac.load(input, clock_select, self.crtc.status.den | self.crtc.in_skew());
 
                         //self.draw_gfx_mode_hchar_6bpp();
                     }
@@ -1203,12 +1235,11 @@ impl EGACard {
                         //self.draw_text_mode_hchar14();
                     }
                     AttributeMode::Graphics => {
-                        let ser = self.gc.serialize(&self.sequencer, self.vma);
-                        self.ac.load(
-                            AttributeInput::Serial(ser),
-                            clock_select,
-                            self.crtc.status.den | self.crtc.in_skew(),
-                        );
+                        let input = match self.gc.fetch(&self.sequencer, self.vma, self.crtc.vlc()) {
+                            GraphicsOutput::Serial(ser) => AttributeInput::Serial(ser),
+                            GraphicsOutput::Parallel(glyph, attr) => AttributeInput::Parallel(glyph, attr, false),
+                        };
+                        self.ac.load(input, clock_select, self.crtc.status.den | self.crtc.in_skew());
 
                         //let out_span = self.get_gfx_mode_lchar_6pp();
                         //self.ac.shift_in(AttributeInput::Serial64(out_span));
@@ -1455,4 +1486,79 @@ mod tests {
         let result = ega.pixel_op_compare();
         assert_eq!(result, 0b00100111);*/
     }
+
+    #[test]
+    fn test_vram_size_64k() {
+        let mut ega = EGACard::new(TraceLogger::None, ClockingMode::Character, false, None, Some(EGA_VRAM_SIZE_64K));
+        assert_eq!(ega.sequencer.vram.plane_size(), 0x4000);
+
+        // All planes enabled, sequential addressing
+        ega.sequencer.write_address(0x02);
+        ega.sequencer.write_data(0x0F);
+        ega.sequencer.write_address(0x04);
+        ega.sequencer.write_data(0x04);
+
+        // A write beyond the installed 16K per plane wraps to the start of the plane
+        ega.sequencer.plane_set(0, 0x4005, 0, 0xA5);
+        assert_eq!(ega.sequencer.vram.read_u8(0, 0x0005), 0xA5);
+        assert_eq!(ega.sequencer.vram.read_u8(0, 0xC005), 0xA5);
+        assert_eq!(ega.sequencer.serialize_linear(0x8005), &[1, 0, 1, 0, 0, 1, 0, 1]);
+
+        // The installed size survives a reset
+        ega.reset_private();
+        assert_eq!(ega.sequencer.vram.plane_size(), 0x4000);
+
+        // A full board does not wrap. The reset cleared the map mask, so enable the planes again.
+        ega.set_vram_size(EGA_VRAM_SIZE_256K);
+        ega.sequencer.write_address(0x02);
+        ega.sequencer.write_data(0x0F);
+        ega.sequencer.write_address(0x04);
+        ega.sequencer.write_data(0x04);
+        ega.sequencer.plane_set(0, 0x4005, 0, 0x5A);
+        assert_eq!(ega.sequencer.vram.read_u8(0, 0x4005), 0x5A);
+        assert_eq!(ega.sequencer.vram.read_u8(0, 0x0005), 0x00);
+    }
+
+    #[test]
+    fn test_rotate_right_u8() {
+        for count in 0..8 {
+            for byte in [0x00, 0x01, 0x80, 0xA5, 0x3C, 0xFF] {
+                assert_eq!(
+                    EGACard::rotate_right_u8(byte, count),
+                    byte.rotate_right(count as u32),
+                    "rotate {:02X} by {}",
+                    byte,
+                    count
+                );
+            }
+        }
+        // Count of 0 is a pass-through, and bits wrap rather than shift out.
+        assert_eq!(EGACard::rotate_right_u8(0x81, 0), 0x81);
+        assert_eq!(EGACard::rotate_right_u8(0x81, 1), 0xC0);
+        assert_eq!(EGACard::rotate_right_u8(0x81, 7), 0x03);
+    }
+
+    #[test]
+    fn test_video_status_mux() {
+        // (color, expected bits 4 & 5 for each Video Status Mux setting)
+        let cases = [(0x2D, [0x30, 0x20, 0x20, 0x00]), (0x1B, [0x10, 0x30, 0x10, 0x00])];
+
+        for (color, expected) in cases {
+            for (mux, bits) in expected.iter().enumerate() {
+                let mut ega = EGACard::default();
+
+                // Select the Color Plane Enable register and set the mux
+                ega.ac.write_attribute_register(0x12);
+                ega.ac.write_attribute_register(0x0F | (mux as u8) << 4);
+
+                // Shift a span of solid color out of the attribute controller
+                ega.ac.load(AttributeInput::SolidColor(color), ClockSelect::Clock16, true);
+                ega.ac.shift_out64();
+                ega.ac.shift_out64();
+
+                let status = ega.read_input_status_register_1();
+                assert_eq!(status & 0x30, *bits, "color {:02X} mux {:02b}", color, mux);
+            }
+        }
+    }
 }
@@ -32,7 +32,7 @@
 
 use crate::{
     device_traits::videocard::VideoCardStateEntry,
-    devices::ega::{tablegen::BIT_EXTEND_TABLE64, vram::Vram, EGA_CHARACTER_HEIGHT},
+    devices::ega::{tablegen::BIT_EXTEND_TABLE64, vram::Vram, EGA_CHARACTER_HEIGHT, EGA_PLANES},
 };
 use modular_bitfield::{bitfield, prelude::*, BitfieldSpecifier};
 
@@ -144,7 +144,9 @@ impl Sequencer {
     }
 
     pub fn reset(&mut self) {
+        let plane_size = self.vram.plane_size();
         *self = Sequencer::default();
+        self.vram.set_plane_size(plane_size);
     }
 
     /// Handle a write to the Sequencer Address register.
@@ -294,6 +296,17 @@ impl Sequencer {
         self.vram.serialize_linear(addr)
     }
 
+    /// Return the raw contents of the specified memory plane, for the debugger's plane viewer.
+    /// Returns None if the plane index is out of range.
+    pub fn plane_dump(&self, plane: usize) -> Option<&[u8]> {
+        if plane < EGA_PLANES {
+            Some(self.vram.plane_slice(plane))
+        }
+        else {
+            None
+        }
+    }
+
     #[inline]
     pub fn plane_set(&mut self, plane: usize, addr: usize, a0: usize, data: u8) {
         // Handle odd/even addressing
@@ -412,7 +412,7 @@ impl VideoCard for EGACard {
     }
 
     fn get_plane_slice(&self, plane: usize) -> &[u8] {
-        self.sequencer.vram.plane_slice(plane)
+        self.sequencer.plane_dump(plane).unwrap_or(&[])
     }
 
     fn dump_mem(&self, path: &Path) {
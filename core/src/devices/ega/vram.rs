@@ -43,6 +43,9 @@ pub struct Vram {
     planes: Box<[[u8; EGA_GFX_PLANE_SIZE]; 4]>,
     linear_buf: Box<[u8; EGA_GFX_PLANE_SIZE * 8]>,
     linear_cga_buf: Box<[u8; EGA_GFX_PLANE_SIZE * 4]>,
+    // Mask applied to plane offsets. Boards with less than 256K installed do not decode the upper
+    // address lines, so accesses beyond the installed plane size wrap.
+    plane_mask: usize,
 }
 
 impl Vram {
@@ -59,40 +62,54 @@ impl Vram {
             .unwrap(),
             linear_buf: vec![0; EGA_GFX_PLANE_SIZE * 8].into_boxed_slice().try_into().unwrap(),
             linear_cga_buf: vec![0; EGA_GFX_PLANE_SIZE * 4].into_boxed_slice().try_into().unwrap(),
+            plane_mask: EGA_GFX_PLANE_SIZE - 1,
         }
     }
 
+    /// Set the installed size of each display plane. The size must be a power of two no larger
+    /// than 64K; a 64K board has 16K per plane, a 128K board 32K and a 256K board the full 64K.
+    pub fn set_plane_size(&mut self, size: usize) {
+        assert!(size.is_power_of_two() && size <= EGA_GFX_PLANE_SIZE);
+        self.plane_mask = size - 1;
+    }
+
+    /// Return the installed size of each display plane.
+    pub fn plane_size(&self) -> usize {
+        self.plane_mask + 1
+    }
+
     #[inline]
     pub fn read_glyph(&self, offset: usize) -> u8 {
-        self.planes[2][offset & 0xFFFF]
+        self.planes[2][offset & self.plane_mask]
     }
 
     #[inline]
     pub fn peek_u8(&self, plane: usize, offset: usize) -> u8 {
-        self.planes[plane][offset & 0xFFFF]
+        self.planes[plane][offset & self.plane_mask]
     }
 
     #[inline]
     pub fn read_u8(&self, plane: usize, offset: usize) -> u8 {
-        self.planes[plane][offset & 0xFFFF]
+        self.planes[plane][offset & self.plane_mask]
     }
 
     #[inline]
     pub fn write_u8(&mut self, plane: usize, offset: usize, data: u8) {
+        let offset = offset & self.plane_mask;
         self.planes[plane][offset] = data;
         self.deplane(offset);
     }
 
     #[inline]
     pub fn read_linear(&self, offset: usize) -> u8 {
-        self.linear_buf[offset]
+        self.linear_buf[offset & (self.plane_mask << 3 | 0x07)]
     }
 
     /// Return a slice of 8 pixels from the linear buffer. This represents serialization of one byte from the
     /// four display planes.
     #[inline]
     pub fn serialize_linear(&self, offset: usize) -> &[u8] {
-        let offset = offset << 3;
+        let offset = (offset & self.plane_mask) << 3;
         &self.linear_buf[offset..offset + 8]
     }
 
@@ -118,18 +135,21 @@ impl Vram {
 
     #[inline]
     pub fn plane_set(&mut self, p: usize, offset: usize, data: u8) {
+        let offset = offset & self.plane_mask;
         self.planes[p][offset] = data;
         self.deplane(offset);
     }
 
     #[inline]
     pub fn plane_and(&mut self, p: usize, offset: usize, data: u8) {
+        let offset = offset & self.plane_mask;
         self.planes[p][offset] &= data;
         self.deplane(offset);
     }
 
     #[inline]
     pub fn plane_or(&mut self, p: usize, offset: usize, data: u8) {
+        let offset = offset & self.plane_mask;
         self.planes[p][offset] |= data;
         self.deplane(offset);
     }
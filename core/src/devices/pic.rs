@@ -553,8 +553,9 @@ impl Pic {
         // If no bit in the IRR was found to be set, then a spurious interrupt occurs.
         // Note that in the event of a spurious interrupt, no bit in the ISR is set to indicate an interrupt is being
         // serviced. This provides a method of determining whether an IR7 is spurious or real.
+        // The spurious vector is IR7's vector, so it is offset by the programmed vector base.
         self.spurious_irqs += 1;
-        Some(SPURIOUS_INTERRUPT)
+        Some(SPURIOUS_INTERRUPT | self.int_offset)
     }
 
     pub fn get_string_state(&self) -> PicStringState {
@@ -51,12 +51,14 @@ pub mod keys;
 pub mod machine;
 pub mod machine_config;
 pub mod memerror;
+pub mod pacing;
 pub mod sound;
 pub mod syntax_token;
 pub mod tracelogger;
 pub mod updatable;
 pub mod util;
 pub mod vhd;
+pub mod video_snapshot;
 
 pub mod cpu_validator; // CpuValidator trait
 
@@ -42,7 +42,7 @@ use std::{
     collections::{HashMap, VecDeque},
     fs::File,
     io::{BufWriter, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 use std::collections::BTreeMap;
 
@@ -66,6 +66,8 @@ use crate::{
     keys::MartyKey,
     machine_config::{get_machine_descriptor, MachineConfiguration, MachineDescriptor},
     machine_types::MachineType,
+    memerror::MemError,
+    pacing,
     sound::{SoundPlayer, BUFFER_MS, VOLUME_ADJUST},
     tracelogger::TraceLogger,
 };
@@ -466,6 +468,7 @@ pub struct Machine {
     error_str: Option<String>,
     turbo_bit: bool,
     turbo_button: bool,
+    warp_mode: bool,
     cpu_factor: ClockFactor,
     next_cpu_factor: ClockFactor,
     cpu_cycles: u64,
@@ -575,6 +578,12 @@ impl Machine {
 
         cpu.set_option(CpuOption::TraceLoggingEnabled(core_config.get_cpu_trace_on()));
 
+        // Apply a custom reset state, if the machine configuration specifies one
+        if let Some(reset_state) = machine_config.cpu.as_ref().and_then(|cpu| cpu.reset.clone()) {
+            cpu.set_reset_state(reset_state);
+            cpu.reset();
+        }
+
         // Set bus options from core configuration now that CPU has created the bus
         cpu.bus_mut().set_options(core_config.get_title_hacks());
 
@@ -699,6 +708,7 @@ impl Machine {
             error_str: None,
             turbo_bit: false,
             turbo_button: false,
+            warp_mode: false,
             cpu_factor,
             next_cpu_factor: cpu_factor,
             cpu_cycles: 0,
@@ -833,6 +843,23 @@ impl Machine {
         Ok(())
     }
 
+    /// Load a raw binary image file into memory at linear address `address`. If `entry` is
+    /// provided, the CPU is reset to begin execution at that segment and offset.
+    pub fn load_image_file(
+        &mut self,
+        path: &Path,
+        address: usize,
+        entry: Option<(u16, u16)>,
+    ) -> Result<usize, MemError> {
+        let size = self.cpu.bus_mut().load_image_file(path, address)?;
+
+        if let Some((segment, offset)) = entry {
+            self.cpu.set_reset_vector(CpuAddress::Segmented(segment, offset));
+            self.cpu.reset();
+        }
+        Ok(size)
+    }
+
     pub fn bus(&self) -> &BusInterface {
         self.cpu.bus()
     }
@@ -940,6 +967,22 @@ impl Machine {
         );
     }
 
+    /// Set warp mode. In warp mode the frontend runs the machine as fast as the host allows.
+    ///
+    /// Device time is unaffected: the DeviceRunTimeUnit::Microseconds deltas passed to devices
+    /// are always derived from the number of emulated CPU cycles, never from host time, so
+    /// emulation proceeds identically, just unthrottled. Audio samples are still produced from
+    /// the PIT buffer but are discarded instead of being queued, so the sound player doesn't
+    /// fall behind. See the pacing module for the helpers that implement this.
+    pub fn set_warp_mode(&mut self, state: bool) {
+        self.warp_mode = state;
+        log::debug!("Set warp mode to: {}", state);
+    }
+
+    pub fn warp_mode(&self) -> bool {
+        self.warp_mode
+    }
+
     pub fn fdc(&mut self) -> &mut Option<FloppyController> {
         self.cpu.bus_mut().fdc_mut()
     }
@@ -1148,22 +1191,14 @@ impl Machine {
     /// Convert a count of CPU cycles to microseconds based on the current CPU clock
     /// divisor and system crystal speed.
     fn cpu_cycles_to_us(&self, cycles: u32) -> f64 {
-        let mhz = match self.cpu_factor {
-            ClockFactor::Divisor(n) => self.machine_desc.system_crystal / (n as f64),
-            ClockFactor::Multiplier(n) => self.machine_desc.system_crystal * (n as f64),
-        };
-
-        1.0 / mhz * cycles as f64
+        pacing::cpu_cycles_to_us(cycles, self.cpu_factor, self.machine_desc.system_crystal)
     }
 
     #[inline]
     /// Convert a count of CPU cycles to system clock ticks based on the current CPU
     /// clock divisor.
     fn cpu_cycles_to_system_ticks(&self, cycles: u32) -> u32 {
-        match self.cpu_factor {
-            ClockFactor::Divisor(n) => cycles * (n as u32),
-            ClockFactor::Multiplier(n) => cycles / (n as u32),
-        }
+        pacing::cpu_cycles_to_system_ticks(cycles, self.cpu_factor)
     }
 
     #[allow(dead_code)]
@@ -1399,7 +1434,12 @@ impl Machine {
                                 exec_control.state = ExecutionState::Halted;
                                 self.error = true;
                                 self.error_str = Some(format!("{}", err));
-                                log::error!("CPU Error: {}\n{}", err, self.cpu.dump_instruction_history_string());
+                                log::error!(
+                                    "CPU Error: {}\n--- Instruction history ---\n{}--- Recent trace ---\n{}",
+                                    err,
+                                    self.cpu.dump_instruction_history_string(),
+                                    self.cpu.dump_recent_trace()
+                                );
                             }
                         }
                     }
@@ -1442,7 +1482,12 @@ impl Machine {
             if let Err(err) = self.cpu.step_finish(listing_entry) {
                 self.error = true;
                 self.error_str = Some(format!("{}", err));
-                log::error!("CPU Error: {}\n{}", err, self.cpu.dump_instruction_history_string());
+                log::error!(
+                    "CPU Error: {}\n--- Instruction history ---\n{}--- Recent trace ---\n{}",
+                    err,
+                    self.cpu.dump_instruction_history_string(),
+                    self.cpu.dump_recent_trace()
+                );
             }
 
             if self.options.record_listing {
@@ -1682,7 +1727,9 @@ impl Machine {
         self.pit_data.samples_produced += 1;
         //log::trace!("producer: {}", self.pit_samples_produced);
         if let Some(sound_player) = &mut self.sound_player {
-            sound_player.queue_sample(average * VOLUME_ADJUST);
+            pacing::route_sample(self.warp_mode, average * VOLUME_ADJUST, |sample| {
+                sound_player.queue_sample(sample)
+            });
         }
 
         // Calculate size of next audio sample in pit samples by carrying over fractional part
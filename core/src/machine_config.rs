@@ -46,7 +46,7 @@ use std::collections::HashMap;
 
 use crate::{
     bus::ClockFactor,
-    cpu_common::CpuType,
+    cpu_common::{CpuResetState, CpuType},
     device_traits::videocard::VideoType,
     devices::{keyboard::KeyboardType, pit::PitType},
     tracelogger::TraceLogger,
@@ -120,6 +120,7 @@ pub enum BusType {
 #[derive(Clone, Debug, Deserialize)]
 pub struct CpuConfig {
     pub upgrade_type: Option<CpuType>,
+    pub reset: Option<CpuResetState>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -171,6 +172,7 @@ pub struct VideoCardConfig {
     #[serde(rename = "subtype")]
     pub video_subtype: Option<VideoCardSubType>,
     pub dip_switch:    Option<u8>,
+    pub vram_size:     Option<u32>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
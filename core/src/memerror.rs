@@ -36,9 +36,11 @@ use std::error::Error;
 #[derive(Debug)]
 pub enum MemError {
     ReadOutOfBoundsError,
+    WriteOutOfBoundsError,
     SeekOutOfBoundsError,
     FileReadError,
     MmioError,
+    ProtectedRegionError,
 }
 impl Error for MemError {}
 impl Display for MemError {
@@ -47,9 +49,15 @@ impl Display for MemError {
             MemError::ReadOutOfBoundsError => {
                 write!(f, "An attempt was made to read out of buffer bounds.")
             }
+            MemError::WriteOutOfBoundsError => {
+                write!(f, "An attempt was made to write out of buffer bounds.")
+            }
             MemError::SeekOutOfBoundsError => write!(f, "An attempt was made to move the buffer cursor out of bounds."),
             MemError::FileReadError => write!(f, "Error reading file into MemBuf."),
             MemError::MmioError => write!(f, "Error accessing map for memory mapped device."),
+            MemError::ProtectedRegionError => {
+                write!(f, "An attempt was made to load data over ROM or a memory mapped device.")
+            }
         }
     }
 }
@@ -0,0 +1,96 @@
+/*
+    MartyPC
+    https://github.com/dbalsom/martypc
+
+    Copyright 2022-2024 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    pacing.rs
+
+    Helpers for pacing the machine against host time. These are kept free of
+    Machine so that warp mode can be tested without a ROM set.
+*/
+
+use std::time::{Duration, Instant};
+
+use crate::bus::ClockFactor;
+
+/// The amount of host time to spend running the machine per frontend update in warp mode.
+pub const WARP_UPDATE_BUDGET: Duration = Duration::from_millis(12);
+
+/// Convert a count of CPU cycles to the microseconds of emulated time that elapse while the
+/// CPU executes them, given the CPU clock factor and the system crystal frequency in MHz.
+///
+/// This is the DeviceRunTimeUnit::Microseconds delta passed to devices. It depends only on
+/// emulated cycles, never on host time, so it is the same whether or not warp mode is enabled.
+#[inline]
+pub fn cpu_cycles_to_us(cycles: u32, cpu_factor: ClockFactor, system_crystal: f64) -> f64 {
+    let mhz = match cpu_factor {
+        ClockFactor::Divisor(n) => system_crystal / (n as f64),
+        ClockFactor::Multiplier(n) => system_crystal * (n as f64),
+    };
+
+    1.0 / mhz * cycles as f64
+}
+
+/// Convert a count of CPU cycles to system clock ticks based on the CPU clock factor.
+#[inline]
+pub fn cpu_cycles_to_system_ticks(cycles: u32, cpu_factor: ClockFactor) -> u32 {
+    match cpu_factor {
+        ClockFactor::Divisor(n) => cycles * (n as u32),
+        ClockFactor::Multiplier(n) => cycles / (n as u32),
+    }
+}
+
+/// Run the machine for one frontend update. `run_batch` runs one batch of the normal cycle
+/// target and returns the number of instructions executed.
+///
+/// Outside of warp mode a single batch is run. In warp mode batches are run back to back until
+/// `budget` of host time has been spent, stopping early if a batch executes nothing, ie, the
+/// machine is paused or hit a breakpoint. Returns the total number of instructions executed.
+pub fn run_batches(warp: bool, budget: Duration, mut run_batch: impl FnMut() -> u64) -> u64 {
+    if !warp {
+        return run_batch();
+    }
+
+    let start = Instant::now();
+    let mut instructions = 0;
+    while start.elapsed() < budget {
+        let executed = run_batch();
+        if executed == 0 {
+            break;
+        }
+        instructions += executed;
+    }
+    instructions
+}
+
+/// Pass a finished audio sample to `queue`, unless in warp mode. In warp mode samples are still
+/// drawn from the PIT buffer but are discarded, so the sound player doesn't build up a backlog.
+/// Returns true if the sample was queued.
+pub fn route_sample(warp: bool, sample: f32, queue: impl FnOnce(f32)) -> bool {
+    if warp {
+        return false;
+    }
+    queue(sample);
+    true
+}
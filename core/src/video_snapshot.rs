@@ -0,0 +1,111 @@
+/*
+    MartyPC
+    https://github.com/dbalsom/martypc
+
+    Copyright 2022-2024 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    video_snapshot.rs
+
+    Helpers for comparing video card display buffers, for video regression
+    tests. These work on the buffer returned by VideoCard::get_display_buf(),
+    so they are usable with any video card.
+*/
+
+/// A snapshot of a display buffer, with the row stride needed to locate pixels.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrameSnapshot {
+    pub buf: Vec<u8>,
+    pub stride: usize,
+}
+
+impl FrameSnapshot {
+    /// Capture a copy of a display buffer. `stride` is the width of a row in bytes, usually
+    /// DisplayExtents::row_stride.
+    pub fn new(buf: &[u8], stride: usize) -> Self {
+        Self {
+            buf: buf.to_vec(),
+            stride,
+        }
+    }
+
+    /// Return a hash of the snapshot contents. See frame_hash() for when a stored hash stays valid.
+    pub fn hash(&self) -> u64 {
+        frame_hash(&self.buf)
+    }
+
+    /// Return the (x, y) coordinate of the first pixel that differs from `other`, or None if
+    /// the snapshots are identical.
+    pub fn diff(&self, other: &FrameSnapshot) -> Option<(usize, usize)> {
+        frame_diff(&self.buf, &other.buf, self.stride)
+    }
+}
+
+/// Return a hash of a display buffer's contents. The hash is stable across runs, but fxhash reads
+/// the buffer in native byte order and doesn't promise the same output between versions, so a stored
+/// hash is only valid on the same endianness and fxhash version.
+pub fn frame_hash(buf: &[u8]) -> u64 {
+    fxhash::hash64(buf)
+}
+
+/// Return the (x, y) coordinate of the first byte that differs between two display buffers of
+/// the given row stride, or None if they are identical. If one buffer is shorter, the first
+/// byte past its end is reported as differing.
+pub fn frame_diff(a: &[u8], b: &[u8], stride: usize) -> Option<(usize, usize)> {
+    let stride = stride.max(1);
+    let index = a
+        .iter()
+        .zip(b.iter())
+        .position(|(a, b)| a != b)
+        .or_else(|| (a.len() != b.len()).then_some(a.len().min(b.len())))?;
+
+    Some((index % stride, index / stride))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_diff() {
+        let (w, h) = (64, 32);
+
+        // Checkerboard of 8x8 cells in two colors.
+        let pattern: Vec<u8> = (0..w * h)
+            .map(|i| if ((i % w) / 8 + (i / w) / 8) % 2 == 0 { 0x0F } else { 0x01 })
+            .collect();
+
+        let frame = FrameSnapshot::new(&pattern, w);
+        let same = FrameSnapshot::new(&pattern, w);
+        assert_eq!(frame.hash(), same.hash());
+        assert_eq!(frame.diff(&same), None);
+
+        let mut mutated = pattern.clone();
+        mutated[17 * w + 42] ^= 0x02;
+        let mutated = FrameSnapshot::new(&mutated, w);
+        assert_ne!(frame.hash(), mutated.hash());
+        assert_eq!(frame.diff(&mutated), Some((42, 17)));
+
+        // A truncated buffer differs at the first missing pixel.
+        assert_eq!(frame_diff(&pattern, &pattern[..w * 3 + 5], w), Some((5, 3)));
+    }
+}
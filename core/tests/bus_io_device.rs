@@ -0,0 +1,197 @@
+/*
+    MartyPC
+    https://github.com/dbalsom/martypc
+
+    Copyright 2022-2024 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+
+    tests::bus_io_device.rs
+
+    Tests for registering custom IO devices on the bus.
+
+*/
+
+mod common;
+
+use std::{cell::RefCell, rc::Rc};
+
+use marty_core::{
+    bus::{BusInterface, DeviceRunTimeUnit, IoDevice, IoTraceDirection, IO_TRACE_LEN},
+    cpu_common::Cpu,
+};
+
+struct MockDevice {
+    writes: Rc<RefCell<Vec<(u16, u8)>>>,
+}
+
+impl IoDevice for MockDevice {
+    fn read_u8(&mut self, port: u16, _delta: DeviceRunTimeUnit) -> u8 {
+        (port & 0xFF) as u8 ^ 0x5A
+    }
+
+    fn write_u8(&mut self, port: u16, data: u8, _bus: Option<&mut BusInterface>, _delta: DeviceRunTimeUnit) {
+        self.writes.borrow_mut().push((port, data));
+    }
+
+    fn port_list(&self) -> Vec<(String, u16)> {
+        vec![
+            (String::from("Mock Data"), 0x188),
+            (String::from("Mock Status"), 0x18A),
+        ]
+    }
+}
+
+#[test]
+fn test_register_io_device() {
+    let mut bus = BusInterface::default();
+    let writes = Rc::new(RefCell::new(Vec::new()));
+
+    let device = MockDevice { writes: writes.clone() };
+    bus.register_io_device(Box::new(device), &[0x188, 0x18A])
+        .expect("Failed to register device");
+
+    assert_eq!(bus.io_read_u8(0x188, 0), 0x88 ^ 0x5A);
+    assert_eq!(bus.io_read_u8(0x18A, 0), 0x8A ^ 0x5A);
+
+    bus.io_write_u8(0x188, 0x12, 0);
+    bus.io_write_u8(0x18A, 0x34, 0);
+    assert_eq!(*writes.borrow(), vec![(0x188, 0x12), (0x18A, 0x34)]);
+
+    // Unregistered ports are unaffected.
+    assert_eq!(bus.io_read_u8(0x189, 0), 0xFF);
+}
+
+#[test]
+fn test_register_io_device_conflict() {
+    let mut bus = BusInterface::default();
+    let writes = Rc::new(RefCell::new(Vec::new()));
+
+    bus.register_io_device(Box::new(MockDevice { writes: writes.clone() }), &[0x188, 0x18C])
+        .expect("Failed to register device");
+
+    // A second device claiming overlapping ports is rejected, reports every overlap, and registers nothing.
+    let result = bus.register_io_device(
+        Box::new(MockDevice { writes: writes.clone() }),
+        &[0x18A, 0x188, 0x18C],
+    );
+    assert_eq!(result, Err(vec![0x188, 0x18C]));
+    assert_eq!(bus.io_read_u8(0x18A, 0), 0xFF);
+}
+
+#[test]
+fn test_io_port_report() {
+    let mut bus = BusInterface::default();
+    let writes = Rc::new(RefCell::new(Vec::new()));
+
+    bus.register_io_device(Box::new(MockDevice { writes: writes.clone() }), &[0x18A, 0x188])
+        .expect("Failed to register device");
+    assert!(bus.io_port_report().conflicts.is_empty());
+
+    // A second device overlapping 0x188 is rejected, and the conflict is reported.
+    let result = bus.register_io_device(Box::new(MockDevice { writes: writes.clone() }), &[0x188, 0x189]);
+    assert_eq!(result, Err(vec![0x188]));
+
+    // Ports are reported by the names the devices gave them, qualified by device, so the rejected
+    // device is distinguishable from the owner even though both use the same port names.
+    let report = bus.io_port_report();
+    assert_eq!(
+        report.ports,
+        vec![
+            (0x188, String::from("Custom device 0: Mock Data")),
+            (0x18A, String::from("Custom device 0: Mock Status")),
+        ]
+    );
+    assert_eq!(
+        report.conflicts,
+        vec![(
+            0x188,
+            String::from("Custom device 0: Mock Data"),
+            String::from("Custom device 1: Mock Data")
+        )]
+    );
+}
+
+#[test]
+fn test_io_trace() {
+    let mut cpu = common::cpu_8088();
+
+    let writes = Rc::new(RefCell::new(Vec::new()));
+    cpu.bus_mut()
+        .register_io_device(Box::new(MockDevice { writes: writes.clone() }), &[0x188, 0x18A])
+        .expect("Failed to register device");
+
+    // 1000:0100  MOV DX, 0188
+    // 1000:0103  MOV AL, 12
+    // 1000:0105  OUT DX, AL
+    // 1000:0106  IN AL, DX
+    // 1000:0107  MOV DX, 018A
+    // 1000:010A  IN AL, DX
+    let program = [0xBA, 0x88, 0x01, 0xB0, 0x12, 0xEE, 0xEC, 0xBA, 0x8A, 0x01, 0xEC];
+    common::boot(&mut cpu, &program);
+    cpu.bus_mut().set_io_trace(true);
+
+    for _ in 0..6 {
+        common::step(&mut cpu);
+    }
+
+    let mut csv = Vec::new();
+    cpu.bus_mut().write_io_trace_csv(&mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0], "sys_tick,direction,port,value");
+    assert!(lines[1].ends_with(",OUT,0188,12"), "{}", lines[1]);
+    assert!(lines[2].ends_with(",IN,0188,D2"), "{}", lines[2]);
+    assert!(lines[3].ends_with(",IN,018A,D0"), "{}", lines[3]);
+
+    let trace = cpu.bus_mut().take_io_trace();
+    let accesses: Vec<_> = trace.iter().map(|e| (e.direction, e.port, e.value)).collect();
+    assert_eq!(
+        accesses,
+        vec![
+            (IoTraceDirection::Out, 0x188, 0x12),
+            (IoTraceDirection::In, 0x188, 0xD2),
+            (IoTraceDirection::In, 0x18A, 0xD0),
+        ]
+    );
+    assert!(cpu.bus_mut().take_io_trace().is_empty());
+
+    // Accesses are not captured once tracing is disabled.
+    cpu.bus_mut().set_io_trace(false);
+    cpu.bus_mut().io_write_u8(0x188, 0x34, 0);
+    assert!(cpu.bus_mut().take_io_trace().is_empty());
+}
+
+#[test]
+fn test_io_trace_capacity() {
+    let mut cpu = common::cpu_8088();
+    cpu.bus_mut().set_io_trace(true);
+
+    for i in 0..IO_TRACE_LEN + 16 {
+        cpu.bus_mut().io_write_u8(0x188, i as u8, 0);
+    }
+
+    // Only the most recent accesses are retained.
+    let trace = cpu.bus_mut().take_io_trace();
+    assert_eq!(trace.len(), IO_TRACE_LEN);
+    assert_eq!(trace[0].value, 16);
+    assert_eq!(trace[IO_TRACE_LEN - 1].value, (IO_TRACE_LEN + 15) as u8);
+}
@@ -0,0 +1,142 @@
+/*
+    MartyPC
+    https://github.com/dbalsom/martypc
+
+    Copyright 2022-2024 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+
+    tests::bus_memory.rs
+
+    Tests for the direct RAM access helpers and image loading on the bus.
+
+*/
+
+mod common;
+
+use marty_core::{
+    bus::BusInterface,
+    cpu_common::{Cpu, CpuAddress, Register16},
+    memerror::MemError,
+};
+
+#[test]
+fn test_fill_and_read_bytes() {
+    let mut bus = BusInterface::default();
+
+    bus.fill(0x1000, 0x100, 0xAA).unwrap();
+    bus.write_bytes(0x1010, &[0x01, 0x02, 0x03, 0x04]).unwrap();
+
+    let bytes = bus.read_bytes(0x0FFF, 0x102).unwrap();
+    assert_eq!(bytes.len(), 0x102);
+    assert_eq!(bytes[0], 0x00);
+    assert!(bytes[1..0x11].iter().all(|b| *b == 0xAA));
+    assert_eq!(&bytes[0x11..0x15], &[0x01, 0x02, 0x03, 0x04]);
+    assert!(bytes[0x15..0x101].iter().all(|b| *b == 0xAA));
+    assert_eq!(bytes[0x101], 0x00);
+
+    // Writes are visible through the normal bus read path for plain RAM
+    assert_eq!(bus.peek_u8(0x1012).unwrap(), 0x03);
+}
+
+#[test]
+fn test_out_of_range() {
+    let mut bus = BusInterface::default();
+    let end = bus.size();
+
+    assert!(matches!(
+        bus.fill(end - 1, 2, 0xFF),
+        Err(MemError::WriteOutOfBoundsError)
+    ));
+    assert!(matches!(
+        bus.write_bytes(end - 2, &[0; 4]),
+        Err(MemError::WriteOutOfBoundsError)
+    ));
+    assert!(matches!(bus.read_bytes(end, 1), Err(MemError::ReadOutOfBoundsError)));
+    assert!(bus.read_bytes(end - 1, 1).is_ok());
+
+    // Ranges that overflow the address calculation are rejected rather than wrapping.
+    assert!(matches!(
+        bus.fill(usize::MAX, 2, 0xFF),
+        Err(MemError::WriteOutOfBoundsError)
+    ));
+    assert!(matches!(
+        bus.write_bytes(usize::MAX, &[0; 2]),
+        Err(MemError::WriteOutOfBoundsError)
+    ));
+    assert!(matches!(
+        bus.read_bytes(usize::MAX, 2),
+        Err(MemError::ReadOutOfBoundsError)
+    ));
+    assert!(matches!(
+        bus.load_image(&[0; 2], usize::MAX),
+        Err(MemError::WriteOutOfBoundsError)
+    ));
+}
+
+#[test]
+fn test_load_image() {
+    let mut bus = BusInterface::default();
+
+    // Images may not overlap ROM.
+    bus.copy_from(&[0xEA; 16], 0xF0000, 0, true).unwrap();
+    assert!(matches!(
+        bus.load_image(&[0x90; 32], 0xEFFF0),
+        Err(MemError::ProtectedRegionError)
+    ));
+    assert!(bus.load_image(&[0x90; 16], 0xEFFF0).is_ok());
+
+    // Or extend past the end of the address space.
+    let end = bus.size();
+    assert!(matches!(
+        bus.load_image(&[0x90; 2], end - 1),
+        Err(MemError::WriteOutOfBoundsError)
+    ));
+}
+
+#[test]
+fn test_load_image_file() {
+    let mut cpu = common::cpu_8088();
+
+    // 1000:0100  MOV AX, BEEF
+    // 1000:0103  MOV [0200], AX
+    let program = [0xB8, 0xEF, 0xBE, 0xA3, 0x00, 0x02];
+    let path = std::env::temp_dir().join(format!("marty_load_image_{}.bin", std::process::id()));
+    std::fs::write(&path, program).unwrap();
+
+    let size = cpu.bus_mut().load_image_file(&path, 0x10100);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(size.unwrap(), program.len());
+
+    cpu.set_reset_vector(CpuAddress::Segmented(0x1000, 0x0100));
+    cpu.reset();
+    cpu.set_register16(Register16::DS, 0x2000);
+    for _ in 0..2 {
+        common::step(&mut cpu);
+    }
+
+    assert_eq!(cpu.bus().read_bytes(0x20200, 2).unwrap(), vec![0xEF, 0xBE]);
+
+    // A missing file is reported as an error.
+    assert!(matches!(
+        cpu.bus_mut().load_image_file(&path, 0x10100),
+        Err(MemError::FileReadError)
+    ));
+}
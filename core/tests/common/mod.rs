@@ -0,0 +1,115 @@
+/*
+    MartyPC
+    https://github.com/dbalsom/martypc
+
+    Copyright 2022-2024 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    ---------------------------------------------------------------------------
+
+    tests::common::mod.rs
+
+    Setup shared by the CPU integration tests. Each test binary uses only some
+    of these helpers, so unused ones are allowed.
+
+*/
+
+#![allow(dead_code)]
+
+use marty_core::{
+    cpu_common::{builder::CpuBuilder, Cpu, CpuAddress, CpuDispatch, CpuType},
+    devices::pic::Pic,
+};
+
+/// Segment and offset that boot() loads programs to and resets to.
+pub const PROGRAM_SEGMENT: u16 = 0x1000;
+pub const PROGRAM_OFFSET: u16 = 0x0100;
+/// Flat address of PROGRAM_SEGMENT:PROGRAM_OFFSET.
+pub const PROGRAM_ADDRESS: usize = 0x10100;
+
+/// The NOPs written by boot_after_nops(). Executing them moves the prefetch queue past the fetches
+/// started by reset.
+const LEAD_IN_NOPS: usize = 4;
+
+/// Build an 8088 with the default options.
+pub fn cpu_8088() -> CpuDispatch {
+    CpuBuilder::new()
+        .with_cpu_type(CpuType::Intel8088)
+        .build()
+        .expect("Failed to build CPU")
+}
+
+/// Write `bytes` to RAM starting at the flat address `address`.
+pub fn write_bytes(cpu: &mut CpuDispatch, address: usize, bytes: &[u8]) {
+    cpu.bus_mut()
+        .write_bytes(address, bytes)
+        .expect("Failed to write memory");
+}
+
+/// Load `program` at 1000:0100 and reset the CPU to execute it.
+pub fn boot(cpu: &mut CpuDispatch, program: &[u8]) {
+    write_bytes(cpu, PROGRAM_ADDRESS, program);
+    cpu.set_reset_vector(CpuAddress::Segmented(PROGRAM_SEGMENT, PROGRAM_OFFSET));
+    cpu.reset();
+}
+
+/// Execute one instruction, including step_finish(), and return the cycles reported by step().
+pub fn step(cpu: &mut CpuDispatch) -> u32 {
+    let (_, cycles) = cpu.step(false).expect("step() failed");
+    cpu.step_finish(None).expect("step_finish() failed");
+    cycles
+}
+
+/// Load `code` at 1000:0100 behind four NOPs and reset the CPU. Use with cycles_after_nops().
+pub fn boot_after_nops(cpu: &mut CpuDispatch, code: &[u8]) {
+    let mut program = vec![0x90; LEAD_IN_NOPS];
+    program.extend_from_slice(code);
+    boot(cpu, &program);
+}
+
+/// Run the NOPs loaded by boot_after_nops(), then execute the first instruction of the code that
+/// follows them and return the cycles it took. The BIU is in the same state after the NOPs for
+/// any `code`, so counts can be compared with each other. The queue is not full, though: NOPs
+/// consume bytes faster than the BIU fetches them, so an instruction that accesses memory may wait
+/// for a fetch in progress. The instruction is not finished.
+pub fn cycles_after_nops(cpu: &mut CpuDispatch) -> u32 {
+    for _ in 0..LEAD_IN_NOPS {
+        step(cpu);
+    }
+    let (_, cycles) = cpu.step(false).expect("step() failed");
+    cycles
+}
+
+/// Install a PIC, initialized for single 8088 mode with edge triggering and the given vector
+/// base for IR0.
+pub fn install_pic(cpu: &mut CpuDispatch, vector_base: u8) {
+    let mut pic = Pic::new();
+    pic.handle_command_register_write(0x13); // ICW1: edge triggered, single, ICW4 needed
+    pic.handle_data_register_write(vector_base); // ICW2: vector base
+    pic.handle_data_register_write(0x01); // ICW4: 8088 mode
+    *cpu.bus_mut().pic_mut() = Some(pic);
+}
+
+/// Point interrupt vector `vector` at `segment:offset`.
+pub fn set_vector(cpu: &mut CpuDispatch, vector: u8, segment: u16, offset: u16) {
+    let [off_lo, off_hi] = offset.to_le_bytes();
+    let [seg_lo, seg_hi] = segment.to_le_bytes();
+    write_bytes(cpu, vector as usize * 4, &[off_lo, off_hi, seg_lo, seg_hi]);
+}
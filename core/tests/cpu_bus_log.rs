@@ -0,0 +1,107 @@
+/*
+    MartyPC
+    https://github.com/dbalsom/martypc
+
+    Copyright 2022-2024 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+
+    tests::cpu_bus_log.rs
+
+    Tests for the bus access log.
+
+*/
+
+mod common;
+
+use marty_core::cpu_common::{BusAccessType, Cpu, CpuDispatch, CpuOption, Register16};
+
+/// Build a CPU running the following program at 1000:0100, with DS = 2000:
+///
+/// 1000:0100  MOV AL, [0200]
+/// 1000:0103  MOV [0201], AL
+/// 1000:0106  NOP
+fn setup() -> CpuDispatch {
+    let mut cpu = common::cpu_8088();
+
+    let program = [0xA0, 0x00, 0x02, 0xA2, 0x01, 0x02, 0x90];
+    common::write_bytes(&mut cpu, 0x20200, &[0x5A]);
+
+    common::boot(&mut cpu, &program);
+    cpu.set_register16(Register16::DS, 0x2000);
+    cpu
+}
+
+fn run(cpu: &mut CpuDispatch, instructions: usize) {
+    for _ in 0..instructions {
+        common::step(cpu);
+    }
+}
+
+#[test]
+fn test_bus_access_log() {
+    let mut cpu = setup();
+    cpu.set_option(CpuOption::BusAccessLogDepth(64));
+    run(&mut cpu, 2);
+
+    let log = cpu.take_bus_access_log();
+    let data: Vec<_> = log
+        .iter()
+        .filter(|e| e.access_type != BusAccessType::CodeFetch)
+        .map(|e| (e.access_type, e.address, e.value, e.cs, e.ip))
+        .collect();
+
+    assert_eq!(
+        data,
+        vec![
+            (BusAccessType::Read, 0x20200, 0x5A, 0x1000, 0x0100),
+            (BusAccessType::Write, 0x20201, 0x5A, 0x1000, 0x0103),
+        ]
+    );
+
+    // Code fetches are logged too, and all of them come from the program
+    let fetches: Vec<_> = log
+        .iter()
+        .filter(|e| e.access_type == BusAccessType::CodeFetch)
+        .collect();
+    assert!(fetches.len() >= 6);
+    assert!(fetches.iter().all(|e| (0x10100..0x10110).contains(&e.address)));
+
+    // Entries are in chronological order
+    assert!(log.windows(2).all(|w| w[0].cycle <= w[1].cycle));
+
+    // Taking the log clears it
+    assert!(cpu.take_bus_access_log().is_empty());
+}
+
+#[test]
+fn test_bus_access_log_depth() {
+    // The log is off by default
+    let mut cpu = setup();
+    run(&mut cpu, 2);
+    assert!(cpu.take_bus_access_log().is_empty());
+
+    // Only the most recent entries are kept
+    let mut cpu = setup();
+    cpu.set_option(CpuOption::BusAccessLogDepth(1));
+    run(&mut cpu, 2);
+    let log = cpu.take_bus_access_log();
+    assert_eq!(log.len(), 1);
+}
@@ -0,0 +1,83 @@
+/*
+    MartyPC
+    https://github.com/dbalsom/martypc
+
+    Copyright 2022-2024 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+
+    tests::cpu_call_stack.rs
+
+    Tests for call stack tracking across nested CALL/CALLF and RET/RETF.
+
+*/
+
+mod common;
+
+use marty_core::cpu_common::{Cpu, Register16};
+
+#[test]
+fn test_call_stack_nested_calls() {
+    let mut cpu = common::cpu_8088();
+
+    // 1000:0100  CALL 0107
+    // 1000:0103  INC AX
+    // 1000:0104  NOP
+    // 1000:0105  NOP
+    // 1000:0106  NOP
+    // 1000:0107  CALL FAR 2000:0000
+    // 1000:010C  RET
+    let program = [0xE8, 0x04, 0x00, 0x40, 0x90, 0x90, 0x90, 0x9A, 0x00, 0x00, 0x00, 0x20, 0xC3];
+    // 2000:0000  INC BX
+    // 2000:0001  RETF
+    let far_proc = [0x43, 0xCB];
+
+    common::write_bytes(&mut cpu, 0x20000, &far_proc);
+
+    common::boot(&mut cpu, &program);
+    cpu.set_register16(Register16::SS, 0x3000);
+    cpu.set_register16(Register16::SP, 0x0100);
+
+    assert!(cpu.get_call_stack().is_empty());
+
+    // CALL, CALLF, INC BX
+    for _ in 0..3 {
+        common::step(&mut cpu);
+    }
+    assert_eq!(cpu.get_register16(Register16::BX), 1);
+    assert_eq!(
+        cpu.get_call_stack(),
+        vec![(0x1000, 0x0103, 0x1000, 0x0107), (0x1000, 0x010C, 0x2000, 0x0000)]
+    );
+
+    // RETF. The stack is unwound once execution reaches the return address.
+    common::step(&mut cpu);
+    assert_eq!(cpu.get_register16(Register16::CS), 0x1000);
+    assert_eq!(cpu.get_ip(), 0x010C);
+
+    // RET
+    common::step(&mut cpu);
+    assert_eq!(cpu.get_call_stack(), vec![(0x1000, 0x0103, 0x1000, 0x0107)]);
+
+    // INC AX
+    common::step(&mut cpu);
+    assert_eq!(cpu.get_register16(Register16::AX), 1);
+    assert!(cpu.get_call_stack().is_empty());
+}
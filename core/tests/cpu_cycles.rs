@@ -0,0 +1,200 @@
+/*
+    MartyPC
+    https://github.com/dbalsom/martypc
+
+    Copyright 2022-2024 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+
+    tests::cpu_cycles.rs
+
+    Tests for the per-instruction cycle counts returned by step().
+
+*/
+
+mod common;
+
+use marty_core::cpu_common::{builder::CpuBuilder, Cpu, CpuType, CycleAdjustments, Register16};
+
+#[test]
+fn test_step_cycle_counts() {
+    let mut cpu = common::cpu_8088();
+
+    // 1000:0100  NOP (x4)
+    // 1000:0104  AAM
+    // 1000:0106  NOP
+    let program = [0x90, 0x90, 0x90, 0x90, 0xD4, 0x0A, 0x90];
+    common::boot(&mut cpu, &program);
+    cpu.set_register16(Register16::AX, 0x0063);
+
+    // First NOP. The count reported by each step() begins with the cycles spent in the previous
+    // step_finish(), so start counting before finishing this one.
+    cpu.step(false).unwrap();
+    let (start_cycles, _) = cpu.get_cycle_ct();
+    cpu.step_finish(None).unwrap();
+
+    // Remaining NOPs
+    let mut total = 0;
+    for _ in 0..3 {
+        total += common::step(&mut cpu);
+    }
+
+    // AAM takes 82 cycles for this operand (the 8088 datasheet gives 83). The reported count
+    // also includes the 2 cycles of the previous NOP's terminating fetch.
+    let (_, aam_cycles) = cpu.step(false).unwrap();
+    total += aam_cycles;
+    assert_eq!(cpu.get_register16(Register16::AX), 0x0909);
+    assert_eq!(aam_cycles, 84);
+
+    // The cycles reported by step() account for every elapsed CPU cycle.
+    let (end_cycles, _) = cpu.get_cycle_ct();
+    assert_eq!(total as u64, end_cycles - start_cycles);
+}
+
+/// Return the cycles taken by `code` with DS at 2000 and BX set to `bx`, after the lead-in NOPs.
+/// Counts taken this way are comparable with each other, but not with datasheet timings.
+fn cycles_after_nops(code: &[u8], bx: u16) -> u32 {
+    let mut cpu = common::cpu_8088();
+    common::boot_after_nops(&mut cpu, code);
+    cpu.set_register16(Register16::DS, 0x2000);
+    cpu.set_register16(Register16::BX, bx);
+    common::cycles_after_nops(&mut cpu)
+}
+
+#[test]
+fn test_word_access_alignment() {
+    // The 8088 always splits a word transfer into two byte transfers, so there is no penalty
+    // for odd addresses, unlike the 8086.
+    for code in [[0x89, 0x07], [0x8B, 0x07]] {
+        let even = cycles_after_nops(&code, 0x0200);
+        let odd = cycles_after_nops(&code, 0x0201);
+        assert_eq!(even, odd, "{:02X?} even: {} odd: {}", code, even, odd);
+    }
+
+    // A word access costs an extra bus cycle over a byte access.
+    let byte = cycles_after_nops(&[0x88, 0x07], 0x0200);
+    let word = cycles_after_nops(&[0x89, 0x07], 0x0200);
+    assert!(word > byte, "byte: {} word: {}", byte, word);
+}
+
+#[test]
+fn test_cycle_adjustments() {
+    let run_aam = |cpu_type: CpuType, adjustments: CycleAdjustments| {
+        let mut cpu = CpuBuilder::new()
+            .with_cpu_type(cpu_type)
+            .with_cycle_adjustments(adjustments)
+            .build()
+            .expect("Failed to build CPU");
+
+        common::boot_after_nops(&mut cpu, &[0xD4, 0x0A]);
+        common::cycles_after_nops(&mut cpu)
+    };
+    let aam_table = |extra: u8| {
+        let mut table = Box::new([0u8; 256]);
+        table[0xD4] = extra;
+        table
+    };
+
+    let stock_8088 = run_aam(CpuType::Intel8088, CycleAdjustments::default());
+    let stock_v20 = run_aam(CpuType::NecV20, CycleAdjustments::default());
+
+    // Slow down AAM by a different amount on each CPU type. AAM runs long enough for the prefetch
+    // queue to fill, so the extra cycles are not absorbed by a pending fetch.
+    let mut adjustments = CycleAdjustments::default();
+    adjustments.set_table(CpuType::Intel8088, aam_table(10));
+    adjustments.set_table(CpuType::NecV20, aam_table(20));
+    assert_eq!(run_aam(CpuType::Intel8088, adjustments.clone()), stock_8088 + 10);
+    assert_eq!(run_aam(CpuType::NecV20, adjustments), stock_v20 + 20);
+
+    // A table for another CPU type is not used.
+    let mut adjustments = CycleAdjustments::default();
+    adjustments.set_table(CpuType::Intel8088, aam_table(10));
+    assert_eq!(run_aam(CpuType::NecV20, adjustments), stock_v20);
+}
+
+#[test]
+fn test_ea_cycle_costs() {
+    // LEA performs the EA calculation without a memory access, so the difference in cycles
+    // between addressing modes is the difference in EA cost. The 8088 datasheet gives 5 cycles
+    // for [BX] or [SI], 7 for [BX+SI] or [BP+DI], and 8 for [BX+DI] or [BP+SI].
+    let base = cycles_after_nops(&[0x8D, 0x07], 0x0200); // LEA AX, [BX]
+    let modes: [(&str, [u8; 2], u32); 5] = [
+        ("[SI]", [0x8D, 0x04], 0),
+        ("[BX+SI]", [0x8D, 0x00], 2),
+        ("[BP+DI]", [0x8D, 0x03], 2),
+        ("[BX+DI]", [0x8D, 0x01], 3),
+        ("[BP+SI]", [0x8D, 0x02], 3),
+    ];
+    for (name, code, extra) in modes {
+        let cycles = cycles_after_nops(&code, 0x0200);
+        assert_eq!(cycles, base + extra, "LEA AX, {} took {} cycles, [BX] took {}", name, cycles, base);
+    }
+
+    // A segment override prefix adds 4 cycles. The datasheet gives 2 for the prefix itself; the rest
+    // comes from the extra byte in the instruction stream. The penalty is the same for LEA, which
+    // makes no memory access.
+    let plain = cycles_after_nops(&[0x8B, 0x07], 0x0200); // MOV AX, [BX]
+    let override_es = cycles_after_nops(&[0x26, 0x8B, 0x07], 0x0200); // MOV AX, ES:[BX]
+    assert_eq!(override_es, plain + 4, "plain: {} override: {}", plain, override_es);
+    let override_lea = cycles_after_nops(&[0x26, 0x8D, 0x07], 0x0200); // LEA AX, ES:[BX]
+    assert_eq!(override_lea, base + 4, "plain: {} override: {}", base, override_lea);
+}
+
+/// Return the cycles taken by `code` with DS at 2000 and BX at 0200, with the prefetch queue full
+/// and the BIU idle, as the datasheet timings assume. After a run of NOPs the BIU is still busy
+/// fetching, so instructions that access memory wait for the bus. A MUL runs first instead: it makes
+/// no bus accesses for long enough that the queue fills.
+fn cycles_with_full_queue(code: &[u8]) -> u32 {
+    let mut cpu = common::cpu_8088();
+
+    // 1000:0100  MOV CL, 00
+    // 1000:0102  MUL CL
+    let mut program = vec![0xB1, 0x00, 0xF6, 0xE1];
+    program.extend_from_slice(code);
+    common::boot(&mut cpu, &program);
+    cpu.set_register16(Register16::DS, 0x2000);
+    cpu.set_register16(Register16::BX, 0x0200);
+
+    common::step(&mut cpu);
+    common::step(&mut cpu);
+    let (_, cycles) = cpu.step(false).expect("step() failed");
+    cycles
+}
+
+#[test]
+fn test_stack_cycle_counts() {
+    // Stack operations on the 8088, with a full prefetch queue. Each word transfer is two 4-cycle
+    // byte transfers, so these are longer than the 8086 figures.
+    //
+    // PUSH ES and PUSHF take a cycle more than the datasheet's 14. PUSH ES runs microcode lines
+    // 0x02C-0x02E, the same length as PUSH reg16's 0x028-0x02A, and PUSHF also spends 3 cycles
+    // before its write, so all three pushes take the same time.
+    let forms: [(&str, &[u8], u32); 5] = [
+        ("PUSH AX", &[0x50], 15),        // Datasheet: 15
+        ("POP AX", &[0x58], 12),         // Datasheet: 12
+        ("PUSH ES", &[0x06], 15),        // Datasheet: 14
+        ("PUSHF", &[0x9C], 15),          // Datasheet: 14
+        ("POP [BX]", &[0x8F, 0x07], 30), // Datasheet: 25 + 5 for the EA
+    ];
+    for (name, code, expected) in forms {
+        let cycles = cycles_with_full_queue(code);
+        assert_eq!(cycles, expected, "{} took {} cycles, expected {}", name, cycles, expected);
+    }
+}
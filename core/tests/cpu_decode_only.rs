@@ -0,0 +1,203 @@
+/*
+    MartyPC
+    https://github.com/dbalsom/martypc
+
+    Copyright 2022-2024 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+
+    tests::cpu_decode_only.rs
+
+    Tests for decode-only mode, which traces instructions without executing their effects.
+
+*/
+
+mod common;
+
+use marty_core::cpu_common::{
+    BusAccessType,
+    Cpu,
+    CpuOption,
+    Mnemonic,
+    Register16,
+    DECODE_TRACE_LEN,
+};
+
+#[test]
+fn test_decode_only_loop() {
+    let mut cpu = common::cpu_8088();
+
+    // 1000:0100  INC AX
+    // 1000:0101  LOOP 0100
+    // 1000:0103  MOV BX, 1234h
+    // 1000:0106  MOV [BX], AL
+    // 1000:0108  NOP
+    let program = [0x40, 0xE2, 0xFD, 0xBB, 0x34, 0x12, 0x88, 0x07, 0x90];
+    common::boot(&mut cpu, &program);
+    cpu.set_register16(Register16::CX, 3);
+    cpu.set_option(CpuOption::DecodeOnly(true));
+
+    for _ in 0..9 {
+        common::step(&mut cpu);
+    }
+
+    let trace: Vec<(u32, Mnemonic)> = cpu
+        .take_decode_trace()
+        .iter()
+        .map(|i| (i.address, i.mnemonic))
+        .collect();
+
+    assert_eq!(
+        trace,
+        vec![
+            (0x10100, Mnemonic::INC),
+            (0x10101, Mnemonic::LOOP),
+            (0x10100, Mnemonic::INC),
+            (0x10101, Mnemonic::LOOP),
+            (0x10100, Mnemonic::INC),
+            (0x10101, Mnemonic::LOOP),
+            (0x10103, Mnemonic::MOV),
+            (0x10106, Mnemonic::MOV),
+            (0x10108, Mnemonic::NOP),
+        ]
+    );
+
+    // LOOP was executed to follow control flow, but no other instruction had any effect.
+    assert_eq!(cpu.get_register16(Register16::CX), 0);
+    assert_eq!(cpu.get_register16(Register16::AX), 0);
+    assert_eq!(cpu.get_register16(Register16::BX), 0);
+    assert_eq!(cpu.bus_mut().read_u8(0x1234, 0).unwrap().0, 0);
+    assert_eq!(cpu.get_ip(), 0x0109);
+
+    // The trace is cleared once taken.
+    assert!(cpu.take_decode_trace().is_empty());
+}
+
+#[test]
+fn test_decode_only_call_side_effects() {
+    let mut cpu = common::cpu_8088();
+
+    // 1000:0100  CALL 0105
+    // 1000:0103  NOP
+    // 1000:0104  NOP
+    // 1000:0105  PUSH AX
+    // 1000:0106  RET
+    let program = [0xE8, 0x02, 0x00, 0x90, 0x90, 0x50, 0xC3];
+    common::boot(&mut cpu, &program);
+    cpu.set_register16(Register16::AX, 0x1234);
+    cpu.set_register16(Register16::SS, 0x3000);
+    cpu.set_register16(Register16::SP, 0x0100);
+    cpu.set_option(CpuOption::DecodeOnly(true));
+
+    for _ in 0..4 {
+        common::step(&mut cpu);
+    }
+
+    let trace: Vec<(u32, Mnemonic)> = cpu
+        .take_decode_trace()
+        .iter()
+        .map(|i| (i.address, i.mnemonic))
+        .collect();
+
+    assert_eq!(
+        trace,
+        vec![
+            (0x10100, Mnemonic::CALL),
+            (0x10105, Mnemonic::PUSH),
+            (0x10106, Mnemonic::RETN),
+            (0x10103, Mnemonic::NOP),
+        ]
+    );
+
+    // CALL and RET are executed to follow control flow, so the return address is written to the
+    // stack and popped again. PUSH AX is skipped, so RET pops the return address.
+    assert_eq!(cpu.bus().read_bytes(0x300FE, 2).unwrap(), vec![0x03, 0x01]);
+    assert_eq!(cpu.bus().read_bytes(0x300FC, 2).unwrap(), vec![0x00, 0x00]);
+    assert_eq!(cpu.get_register16(Register16::SP), 0x0100);
+}
+
+#[test]
+fn test_decode_only_operand_loads() {
+    let mut cpu = common::cpu_8088();
+
+    // 1000:0100  ADD AX, [BX]
+    // 1000:0102  JMP [BX]
+    // 1000:0110  NOP
+    let mut program = vec![0x03, 0x07, 0xFF, 0x27];
+    program.resize(0x11, 0x90);
+    common::boot(&mut cpu, &program);
+    common::write_bytes(&mut cpu, 0x20200, &[0x10, 0x01]);
+    cpu.set_register16(Register16::DS, 0x2000);
+    cpu.set_register16(Register16::BX, 0x0200);
+    cpu.set_option(CpuOption::BusAccessLogDepth(64));
+    cpu.set_option(CpuOption::DecodeOnly(true));
+
+    for _ in 0..3 {
+        common::step(&mut cpu);
+    }
+
+    let trace: Vec<(u32, Mnemonic)> = cpu
+        .take_decode_trace()
+        .iter()
+        .map(|i| (i.address, i.mnemonic))
+        .collect();
+    assert_eq!(
+        trace,
+        vec![(0x10100, Mnemonic::ADD), (0x10102, Mnemonic::JMP), (0x10110, Mnemonic::NOP)]
+    );
+
+    // ADD is skipped without reading its operand. JMP [BX] is executed, so it reads its target.
+    let reads: Vec<_> = cpu
+        .take_bus_access_log()
+        .iter()
+        .filter(|e| e.access_type != BusAccessType::CodeFetch)
+        .map(|e| (e.access_type, e.address, e.ip))
+        .collect();
+    assert_eq!(
+        reads,
+        vec![
+            (BusAccessType::Read, 0x20200, 0x0102),
+            (BusAccessType::Read, 0x20201, 0x0102),
+        ]
+    );
+    assert_eq!(cpu.get_register16(Register16::AX), 0);
+}
+
+#[test]
+fn test_decode_only_trace_capacity() {
+    let mut cpu = common::cpu_8088();
+
+    // 1000:0100  INC AX
+    // 1000:0101  JMP 0100
+    let program = [0x40, 0xEB, 0xFD];
+    common::boot(&mut cpu, &program);
+    cpu.set_option(CpuOption::DecodeOnly(true));
+
+    for _ in 0..DECODE_TRACE_LEN + 11 {
+        common::step(&mut cpu);
+    }
+
+    // Only the most recent instructions are retained, ending with the last INC.
+    let trace = cpu.take_decode_trace();
+    assert_eq!(trace.len(), DECODE_TRACE_LEN);
+    assert_eq!(trace[0].mnemonic, Mnemonic::JMP);
+    assert_eq!(trace[DECODE_TRACE_LEN - 1].mnemonic, Mnemonic::INC);
+    assert_eq!(cpu.get_register16(Register16::AX), 0);
+}
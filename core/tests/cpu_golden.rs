@@ -0,0 +1,263 @@
+/*
+    MartyPC
+    https://github.com/dbalsom/martypc
+
+    Copyright 2022-2024 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    ---------------------------------------------------------------------------
+
+    tests::cpu_golden.rs
+
+    Golden single-instruction regression tests for the 808x CPU core.
+
+    Each JSON file in tests/golden contains an array of test cases, see
+    tests/golden/README.md for the file format. Every case is executed from
+    a fresh reset, and the final register and memory state and the cycle count
+    are compared against the expected values. If any case fails, the failures
+    are reported along with a pass/fail count for each opcode.
+
+    The register structures are borrowed from the cpu_validator module, but no
+    validator is used, so these tests run without the cpu_validator feature.
+
+*/
+
+mod common;
+
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use serde_derive::Deserialize;
+
+use marty_core::{
+    cpu_common::{Cpu, CpuAddress, CpuDispatch, CpuOption, Register16},
+    cpu_validator::{VRegisters, VRegistersDelta},
+};
+
+#[derive(Deserialize)]
+struct GoldenState {
+    regs: VRegisters,
+    #[serde(default)]
+    ram:  Vec<[u32; 2]>,
+}
+
+#[derive(Deserialize)]
+struct GoldenFinalState {
+    regs: VRegistersDelta,
+    #[serde(default)]
+    ram:  Vec<[u32; 2]>,
+}
+
+#[derive(Deserialize)]
+struct GoldenTest {
+    name: String,
+    bytes: Vec<u8>,
+    initial: GoldenState,
+    #[serde(rename = "final")]
+    final_state: GoldenFinalState,
+    #[serde(default = "default_flags_mask")]
+    flags_mask: u16,
+    cycles: u64,
+}
+
+fn default_flags_mask() -> u16 {
+    0xFFFF
+}
+
+const PREFIXES: [u8; 8] = [0x26, 0x2E, 0x36, 0x3E, 0xF0, 0xF1, 0xF2, 0xF3];
+
+#[derive(Default)]
+struct OpcodeTally {
+    pass: usize,
+    fail: usize,
+}
+
+/// Return the first non-prefix byte of an instruction, used to tally results per opcode.
+fn opcode_of(bytes: &[u8]) -> u8 {
+    bytes.iter().copied().find(|b| !PREFIXES.contains(b)).unwrap_or(0)
+}
+
+fn golden_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden")
+}
+
+fn get_registers(cpu: &mut CpuDispatch) -> VRegisters {
+    VRegisters {
+        ax:    cpu.get_register16(Register16::AX),
+        bx:    cpu.get_register16(Register16::BX),
+        cx:    cpu.get_register16(Register16::CX),
+        dx:    cpu.get_register16(Register16::DX),
+        cs:    cpu.get_register16(Register16::CS),
+        ss:    cpu.get_register16(Register16::SS),
+        ds:    cpu.get_register16(Register16::DS),
+        es:    cpu.get_register16(Register16::ES),
+        sp:    cpu.get_register16(Register16::SP),
+        bp:    cpu.get_register16(Register16::BP),
+        si:    cpu.get_register16(Register16::SI),
+        di:    cpu.get_register16(Register16::DI),
+        ip:    cpu.get_ip(),
+        flags: cpu.get_flags(),
+    }
+}
+
+/// Run a single test case, returning a list of mismatches.
+fn run_test(test: &GoldenTest) -> Vec<String> {
+    let mut cpu = common::cpu_8088();
+
+    let regs = &test.initial.regs;
+    cpu.set_reset_vector(CpuAddress::Segmented(regs.cs, regs.ip));
+    cpu.reset();
+    cpu.set_option(CpuOption::EnableWaitStates(false));
+
+    cpu.set_register16(Register16::AX, regs.ax);
+    cpu.set_register16(Register16::BX, regs.bx);
+    cpu.set_register16(Register16::CX, regs.cx);
+    cpu.set_register16(Register16::DX, regs.dx);
+    cpu.set_register16(Register16::SP, regs.sp);
+    cpu.set_register16(Register16::BP, regs.bp);
+    cpu.set_register16(Register16::SI, regs.si);
+    cpu.set_register16(Register16::DI, regs.di);
+    cpu.set_register16(Register16::ES, regs.es);
+    cpu.set_register16(Register16::SS, regs.ss);
+    cpu.set_register16(Register16::DS, regs.ds);
+    cpu.set_flags(regs.flags);
+
+    // Load instruction bytes at CS:IP, then any additional memory.
+    let code_base = ((regs.cs as usize) << 4) + regs.ip as usize;
+    for (i, byte) in test.bytes.iter().enumerate() {
+        cpu.bus_mut()
+            .write_u8((code_base + i) & 0xFFFFF, *byte, 0)
+            .expect("Failed to write memory");
+    }
+    for [addr, byte] in &test.initial.ram {
+        cpu.bus_mut()
+            .write_u8(*addr as usize, *byte as u8, 0)
+            .expect("Failed to write memory");
+    }
+
+    // REP-prefixed string instructions execute one iteration per step. Cycles are counted from reset until the
+    // instruction completes, so they include the initial queue fill but not the terminating fetch.
+    let start_cycles = cpu.get_cycle_ct().0;
+    loop {
+        if let Err(e) = cpu.step(false) {
+            return vec![format!("CPU error: {}", e)];
+        }
+        if !cpu.in_rep() {
+            break;
+        }
+    }
+    let cycles = cpu.get_cycle_ct().0 - start_cycles;
+    _ = cpu.step_finish(None);
+
+    let mut errors = Vec::new();
+    let expected = test.initial.regs.apply_delta(&test.final_state.regs);
+    let actual = get_registers(&mut cpu);
+
+    let reg_pairs = [
+        ("ax", expected.ax, actual.ax),
+        ("bx", expected.bx, actual.bx),
+        ("cx", expected.cx, actual.cx),
+        ("dx", expected.dx, actual.dx),
+        ("cs", expected.cs, actual.cs),
+        ("ss", expected.ss, actual.ss),
+        ("ds", expected.ds, actual.ds),
+        ("es", expected.es, actual.es),
+        ("sp", expected.sp, actual.sp),
+        ("bp", expected.bp, actual.bp),
+        ("si", expected.si, actual.si),
+        ("di", expected.di, actual.di),
+        ("ip", expected.ip, actual.ip),
+    ];
+    for (name, e, a) in reg_pairs {
+        if e != a {
+            errors.push(format!("{}: expected {:04X} got {:04X}", name, e, a));
+        }
+    }
+    if (expected.flags & test.flags_mask) != (actual.flags & test.flags_mask) {
+        errors.push(format!(
+            "flags: expected {:016b} got {:016b} (mask {:016b})",
+            expected.flags, actual.flags, test.flags_mask
+        ));
+    }
+
+    if cycles != test.cycles {
+        errors.push(format!("cycles: expected {} got {}", test.cycles, cycles));
+    }
+
+    for [addr, byte] in &test.final_state.ram {
+        let actual_byte = cpu.bus().peek_u8(*addr as usize).unwrap_or(0);
+        if actual_byte != *byte as u8 {
+            errors.push(format!(
+                "mem [{:05X}]: expected {:02X} got {:02X}",
+                addr, byte, actual_byte
+            ));
+        }
+    }
+
+    errors
+}
+
+#[test]
+fn test_golden_corpus() {
+    let mut paths: Vec<PathBuf> = fs::read_dir(golden_dir())
+        .expect("Failed to read golden test directory")
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty(), "No golden test files found");
+
+    let mut tally: BTreeMap<u8, OpcodeTally> = BTreeMap::new();
+    let mut failures = Vec::new();
+
+    for path in paths {
+        let json = fs::read_to_string(&path).expect("Failed to read golden test file");
+        let tests: Vec<GoldenTest> =
+            serde_json::from_str(&json).unwrap_or_else(|e| panic!("Failed to parse {}: {}", path.display(), e));
+
+        for test in &tests {
+            let entry = tally.entry(opcode_of(&test.bytes)).or_default();
+            let errors = run_test(test);
+            if errors.is_empty() {
+                entry.pass += 1;
+            }
+            else {
+                entry.fail += 1;
+                failures.push(format!(
+                    "{}: {} ({:02X?})\n    {}",
+                    path.file_name().unwrap().to_string_lossy(),
+                    test.name,
+                    test.bytes,
+                    errors.join("\n    ")
+                ));
+            }
+        }
+    }
+
+    let summary: Vec<String> = tally
+        .iter()
+        .map(|(opcode, t)| format!("{:02X}: {} passed, {} failed", opcode, t.pass, t.fail))
+        .collect();
+    assert!(
+        failures.is_empty(),
+        "Golden tests failed:\n{}\n\nResults by opcode:\n{}",
+        failures.join("\n"),
+        summary.join("\n")
+    );
+}
@@ -0,0 +1,87 @@
+/*
+    MartyPC
+    https://github.com/dbalsom/martypc
+
+    Copyright 2022-2024 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    ---------------------------------------------------------------------------
+
+    tests::cpu_halt.rs
+
+    Tests for HLT and resuming from halt on interrupt.
+
+*/
+
+mod common;
+
+use marty_core::cpu_common::{Cpu, Register16};
+
+#[test]
+fn test_halt_resume_on_intr() {
+    let mut cpu = common::cpu_8088();
+
+    // 1000:0100  STI
+    // 1000:0101  HLT
+    // 1000:0102  INC AX
+    let program = [0xFB, 0xF4, 0x40];
+    // 2000:0000  INC BX
+    // 2000:0001  IRET
+    let isr = [0x43, 0xCF];
+
+    common::write_bytes(&mut cpu, 0x20000, &isr);
+    // With no PIC installed, INTR is acknowledged as vector 7. Point it at the ISR.
+    common::set_vector(&mut cpu, 7, 0x2000, 0x0000);
+
+    common::boot(&mut cpu, &program);
+    cpu.set_register16(Register16::SS, 0x3000);
+    cpu.set_register16(Register16::SP, 0x0100);
+
+    // STI, HLT
+    for _ in 0..2 {
+        common::step(&mut cpu);
+    }
+
+    // While halted, stepping only spends cycles.
+    for _ in 0..4 {
+        common::step(&mut cpu);
+    }
+    assert_eq!(cpu.get_register16(Register16::CS), 0x1000);
+    assert_eq!(cpu.get_register16(Register16::BX), 0);
+
+    // Raise INTR. The CPU should leave halt and enter the ISR.
+    cpu.step(false).unwrap();
+    cpu.set_intr(true);
+    cpu.step_finish(None).unwrap();
+    cpu.set_intr(false);
+    assert_eq!(cpu.get_register16(Register16::CS), 0x2000);
+
+    // INC BX, IRET
+    for _ in 0..2 {
+        common::step(&mut cpu);
+    }
+    assert_eq!(cpu.get_register16(Register16::BX), 1);
+
+    // Execution resumes at the instruction after HLT.
+    assert_eq!(cpu.get_register16(Register16::CS), 0x1000);
+    assert_eq!(cpu.get_ip(), 0x0102);
+    common::step(&mut cpu);
+    assert_eq!(cpu.get_register16(Register16::AX), 1);
+}
@@ -0,0 +1,101 @@
+/*
+    MartyPC
+    https://github.com/dbalsom/martypc
+
+    Copyright 2022-2024 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+
+    tests::cpu_instruction_hook.rs
+
+    Tests for the instruction hook callback.
+
+*/
+
+mod common;
+
+use marty_core::cpu_common::{Cpu, CpuDispatch, HookAction, Register16, StepResult};
+
+/// Build a CPU running the following program at 1000:0100:
+///
+/// 1000:0100  MOV AX, 0000
+/// 1000:0103  MOV BX, AX
+/// 1000:0105  NOP
+fn setup() -> CpuDispatch {
+    let mut cpu = common::cpu_8088();
+
+    let program = [0xB8, 0x00, 0x00, 0x89, 0xC3, 0x90];
+    common::boot(&mut cpu, &program);
+    cpu
+}
+
+fn step(cpu: &mut CpuDispatch, skip_breakpoint: bool) -> StepResult {
+    let (result, _) = cpu.step(skip_breakpoint).unwrap();
+    if let StepResult::Normal = result {
+        cpu.step_finish(None).unwrap();
+    }
+    result
+}
+
+#[test]
+fn test_hook_modifies_register() {
+    let mut cpu = setup();
+
+    cpu.set_instruction_hook(Some(Box::new(|cpu: &mut dyn Cpu, cs: u16, ip: u16, opcode: u8| {
+        if cs == 0x1000 && ip == 0x0103 {
+            assert_eq!(opcode, 0x89);
+            cpu.set_register16(Register16::AX, 0x1234);
+        }
+        HookAction::Continue
+    })));
+
+    step(&mut cpu, false);
+    assert_eq!(cpu.get_register16(Register16::AX), 0x0000);
+    step(&mut cpu, false);
+
+    // MOV BX, AX saw the value written by the hook
+    assert_eq!(cpu.get_register16(Register16::BX), 0x1234);
+}
+
+#[test]
+fn test_hook_break() {
+    let mut cpu = setup();
+
+    cpu.set_instruction_hook(Some(Box::new(|_cpu: &mut dyn Cpu, _cs: u16, ip: u16, _opcode: u8| {
+        if ip == 0x0103 {
+            HookAction::Break
+        }
+        else {
+            HookAction::Continue
+        }
+    })));
+    cpu.set_register16(Register16::BX, 0xFFFF);
+
+    assert!(matches!(step(&mut cpu, false), StepResult::Normal));
+    assert!(matches!(step(&mut cpu, false), StepResult::BreakpointHit));
+
+    // MOV BX, AX has not executed yet
+    assert_eq!(cpu.get_register16(Register16::BX), 0xFFFF);
+
+    // Resume as the debugger does after a breakpoint
+    cpu.clear_breakpoint_flag();
+    assert!(matches!(step(&mut cpu, true), StepResult::Normal));
+    assert_eq!(cpu.get_register16(Register16::BX), 0x0000);
+}
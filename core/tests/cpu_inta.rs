@@ -0,0 +1,224 @@
+/*
+    MartyPC
+    https://github.com/dbalsom/martypc
+
+    Copyright 2022-2024 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+
+    tests::cpu_inta.rs
+
+    Tests for maskable interrupts acknowledged through the PIC.
+
+*/
+
+mod common;
+
+use marty_core::cpu_common::{Cpu, Register16};
+
+#[test]
+fn test_intr_vector_from_pic() {
+    let mut cpu = common::cpu_8088();
+
+    // Program the PIC with a vector base of 0x50 instead of the usual 0x08.
+    common::install_pic(&mut cpu, 0x50);
+
+    // 1000:0100  NOP
+    // 1000:0101  INC AX
+    let program = [0x90, 0x40];
+    // 2000:0000  INC BX
+    // 2000:0001  IRET
+    let isr = [0x43, 0xCF];
+    // 3000:0000  INC CX
+    // 3000:0001  IRET
+    let wrong_isr = [0x41, 0xCF];
+
+    common::write_bytes(&mut cpu, 0x20000, &isr);
+    common::write_bytes(&mut cpu, 0x30000, &wrong_isr);
+    // IR1 should be delivered as INT 51h. Point the IRQ1 vector for the default base (INT 09h)
+    // somewhere else, so we can tell if the base was ignored.
+    common::set_vector(&mut cpu, 0x51, 0x2000, 0x0000);
+    common::set_vector(&mut cpu, 0x09, 0x3000, 0x0000);
+
+    common::boot(&mut cpu, &program);
+    cpu.set_register16(Register16::SS, 0x4000);
+    cpu.set_register16(Register16::SP, 0x0100);
+    cpu.set_flags(cpu.get_flags() | 0x0200);
+
+    // Raise IR1 on the PIC and INTR on the CPU, then execute the NOP. The interrupt is taken in
+    // step_finish(), which performs the INTA bus cycles and reads the vector from the PIC.
+    cpu.bus_mut().pic_mut().as_mut().unwrap().request_interrupt(1);
+    cpu.set_intr(true);
+    common::step(&mut cpu);
+    cpu.set_intr(false);
+
+    assert_eq!(cpu.get_register16(Register16::CS), 0x2000);
+    assert_eq!(cpu.get_ip(), 0x0000);
+    // The interrupt was acknowledged, so the PIC has dropped INTR.
+    assert!(!cpu.bus_mut().pic_mut().as_ref().unwrap().query_interrupt_line());
+
+    // INC BX, IRET
+    for _ in 0..2 {
+        common::step(&mut cpu);
+    }
+    assert_eq!(cpu.get_register16(Register16::BX), 1);
+    assert_eq!(cpu.get_register16(Register16::CX), 0);
+    assert_eq!(cpu.get_register16(Register16::CS), 0x1000);
+    assert_eq!(cpu.get_ip(), 0x0101);
+}
+
+#[test]
+fn test_intr_during_rep() {
+    let mut cpu = common::cpu_8088();
+    common::install_pic(&mut cpu, 0x50);
+
+    // 1000:0100  REP MOVSW
+    // 1000:0102  NOP
+    let program = [0xF3, 0xA5, 0x90];
+    // 2000:0000  INC BX
+    // 2000:0001  IRET
+    let isr = [0x43, 0xCF];
+
+    common::write_bytes(&mut cpu, 0x20000, &isr);
+    common::set_vector(&mut cpu, 0x51, 0x2000, 0x0000);
+    // Source words at 5000:0000
+    let source: Vec<u8> = (0..0x80).map(|i| i as u8 ^ 0xA5).collect();
+    common::write_bytes(&mut cpu, 0x50000, &source);
+
+    common::boot(&mut cpu, &program);
+    cpu.set_register16(Register16::SS, 0x4000);
+    cpu.set_register16(Register16::SP, 0x0100);
+    cpu.set_register16(Register16::DS, 0x5000);
+    cpu.set_register16(Register16::ES, 0x6000);
+    cpu.set_register16(Register16::CX, 0x0040);
+    cpu.set_flags(cpu.get_flags() | 0x0200);
+
+    // Each step() runs one iteration of the REP. Raise IR1 partway through the copy. IP already
+    // points past the REP MOVSW while it is in progress, so check in_rep() to find the end.
+    let mut isr_cx = None;
+    let mut steps = 0;
+    while cpu.in_rep() || !(cpu.get_register16(Register16::CS) == 0x1000 && cpu.get_ip() == 0x0102) {
+        assert!(steps < 200, "REP MOVSW did not complete");
+        cpu.step(false).unwrap();
+        if steps == 10 {
+            cpu.bus_mut().pic_mut().as_mut().unwrap().request_interrupt(1);
+            cpu.set_intr(true);
+        }
+        cpu.step_finish(None).unwrap();
+        steps += 1;
+
+        if cpu.get_register16(Register16::CS) == 0x2000 && isr_cx.is_none() {
+            // The interrupt was taken between iterations, with the REP still in progress.
+            isr_cx = Some(cpu.get_register16(Register16::CX));
+            cpu.set_intr(false);
+        }
+    }
+
+    let isr_cx = isr_cx.expect("ISR did not run");
+    assert!(isr_cx > 0 && isr_cx < 0x40, "CX was {:04X} when the ISR ran", isr_cx);
+    assert_eq!(cpu.get_register16(Register16::BX), 1);
+
+    // The REP resumed after IRET and completed the copy.
+    assert_eq!(cpu.get_register16(Register16::CX), 0);
+    assert_eq!(cpu.get_register16(Register16::SI), 0x0080);
+    assert_eq!(cpu.get_register16(Register16::DI), 0x0080);
+    for i in 0..0x80 {
+        let (byte, _) = cpu.bus_mut().read_u8(0x60000 + i, 0).unwrap();
+        assert_eq!(byte, i as u8 ^ 0xA5, "destination byte {:02X}", i);
+    }
+}
+
+#[test]
+fn test_intr_rep_movsw_restart() {
+    let mut cpu = common::cpu_8088();
+    common::install_pic(&mut cpu, 0x50);
+
+    // 1000:0100  REP MOVSW
+    // 1000:0102  NOP
+    let program = [0xF3, 0xA5, 0x90];
+    // 2000:0000  INC BX
+    // 2000:0001  IRET
+    let isr = [0x43, 0xCF];
+
+    common::write_bytes(&mut cpu, 0x20000, &isr);
+    common::set_vector(&mut cpu, 0x51, 0x2000, 0x0000);
+    // Source words at 5000:0010
+    let source: Vec<u8> = (0..0x10).map(|i| 0x80 | i as u8).collect();
+    common::write_bytes(&mut cpu, 0x50010, &source);
+
+    common::boot(&mut cpu, &program);
+    cpu.set_register16(Register16::SS, 0x4000);
+    cpu.set_register16(Register16::SP, 0x0100);
+    cpu.set_register16(Register16::DS, 0x5000);
+    cpu.set_register16(Register16::ES, 0x6000);
+    cpu.set_register16(Register16::SI, 0x0010);
+    cpu.set_register16(Register16::DI, 0x0020);
+    cpu.set_register16(Register16::CX, 0x0008);
+    cpu.set_flags(cpu.get_flags() | 0x0200);
+
+    // Raise IR1 after a few iterations. The REP is suspended between iterations, and the return
+    // address pushed for the interrupt points back at the REP prefix so that it restarts.
+    let mut interrupted = false;
+    let mut steps = 0;
+    while cpu.in_rep() || !(cpu.get_register16(Register16::CS) == 0x1000 && cpu.get_ip() == 0x0102) {
+        assert!(steps < 50, "REP MOVSW did not complete");
+        cpu.step(false).unwrap();
+        if steps == 3 {
+            cpu.bus_mut().pic_mut().as_mut().unwrap().request_interrupt(1);
+            cpu.set_intr(true);
+        }
+        cpu.step_finish(None).unwrap();
+        steps += 1;
+
+        if cpu.get_register16(Register16::CS) == 0x2000 && !interrupted {
+            interrupted = true;
+            cpu.set_intr(false);
+
+            let cx = cpu.get_register16(Register16::CX);
+            let done = 0x0008 - cx;
+            assert!(cx > 0 && cx < 0x0008, "CX was {:04X} when the ISR ran", cx);
+            assert_eq!(cpu.get_register16(Register16::SI), 0x0010 + done * 2);
+            assert_eq!(cpu.get_register16(Register16::DI), 0x0020 + done * 2);
+
+            // The stack holds IP, CS and flags. IP is the address of the REP prefix.
+            let sp = cpu.get_register16(Register16::SP) as usize;
+            let (ip_lo, _) = cpu.bus_mut().read_u8(0x40000 + sp, 0).unwrap();
+            let (ip_hi, _) = cpu.bus_mut().read_u8(0x40000 + sp + 1, 0).unwrap();
+            let (cs_lo, _) = cpu.bus_mut().read_u8(0x40000 + sp + 2, 0).unwrap();
+            let (cs_hi, _) = cpu.bus_mut().read_u8(0x40000 + sp + 3, 0).unwrap();
+            assert_eq!(u16::from_le_bytes([ip_lo, ip_hi]), 0x0100);
+            assert_eq!(u16::from_le_bytes([cs_lo, cs_hi]), 0x1000);
+        }
+    }
+
+    assert!(interrupted, "ISR did not run");
+    assert_eq!(cpu.get_register16(Register16::BX), 1);
+
+    // The restarted REP completed the remaining iterations without repeating any.
+    assert_eq!(cpu.get_register16(Register16::CX), 0);
+    assert_eq!(cpu.get_register16(Register16::SI), 0x0020);
+    assert_eq!(cpu.get_register16(Register16::DI), 0x0030);
+    for i in 0..0x10 {
+        let (byte, _) = cpu.bus_mut().read_u8(0x60020 + i, 0).unwrap();
+        assert_eq!(byte, 0x80 | i as u8, "destination byte {:02X}", i);
+    }
+    let (byte, _) = cpu.bus_mut().read_u8(0x60030, 0).unwrap();
+    assert_eq!(byte, 0x00, "REP MOVSW copied past the end of the buffer");
+}
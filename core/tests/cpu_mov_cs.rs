@@ -0,0 +1,72 @@
+/*
+    MartyPC
+    https://github.com/dbalsom/martypc
+
+    Copyright 2022-2024 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    ---------------------------------------------------------------------------
+
+    tests::cpu_mov_cs.rs
+
+    Tests for MOV CS, r/m16, which the 8088 executes as an unflushed jump.
+
+*/
+
+mod common;
+
+use marty_core::cpu_common::{Cpu, Register16};
+
+#[test]
+fn test_mov_cs() {
+    let mut cpu = common::cpu_8088();
+
+    // 1000:0100  MOV AX, 2000
+    // 1000:0103  MOV CS, AX
+    // 1000:0105  INC BX (x8)
+    let program = [0xB8, 0x00, 0x20, 0x8E, 0xC8];
+    common::write_bytes(&mut cpu, 0x10105, &[0x43; 8]);
+    // 2000:0105  INC DX (x8)
+    common::write_bytes(&mut cpu, 0x20105, &[0x42; 8]);
+
+    common::boot(&mut cpu, &program);
+
+    for _ in 0..2 {
+        common::step(&mut cpu);
+    }
+
+    // CS is loaded, but IP is not changed.
+    assert_eq!(cpu.get_register16(Register16::CS), 0x2000);
+    assert_eq!(cpu.get_ip(), 0x0105);
+
+    for _ in 0..8 {
+        common::step(&mut cpu);
+    }
+
+    // The prefetch queue is not flushed, so any bytes already fetched from the old code segment
+    // execute first. Fetching then continues at the same IP in the new code segment.
+    let old_segment = cpu.get_register16(Register16::BX);
+    let new_segment = cpu.get_register16(Register16::DX);
+    assert!(old_segment <= 4, "executed {} bytes from the old segment", old_segment);
+    assert!(new_segment > 0);
+    assert_eq!(old_segment + new_segment, 8);
+    assert_eq!(cpu.get_register16(Register16::CS), 0x2000);
+    assert_eq!(cpu.get_ip(), 0x010D);
+}
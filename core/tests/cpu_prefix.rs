@@ -0,0 +1,136 @@
+/*
+    MartyPC
+    https://github.com/dbalsom/martypc
+
+    Copyright 2022-2024 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+
+    tests::cpu_prefix.rs
+
+    Tests for instructions carrying multiple segment override prefixes.
+
+*/
+
+mod common;
+
+use marty_core::{
+    breakpoints::BreakPointType,
+    cpu_common::{Cpu, Register16, StepResult},
+};
+
+/// Run a single instruction at 1000:0100 that loads a word from [BX], and return the value
+/// loaded into AX along with the cycles the instruction took.
+fn run_load(code: &[u8]) -> (u16, u32) {
+    let mut cpu = common::cpu_8088();
+
+    // Distinct words at CS:0200 and DS:0200, so we can tell which segment was used.
+    common::write_bytes(&mut cpu, 0x10200, &[0x34, 0x12]);
+    common::write_bytes(&mut cpu, 0x20200, &[0x78, 0x56]);
+
+    common::boot(&mut cpu, code);
+    cpu.set_register16(Register16::DS, 0x2000);
+    cpu.set_register16(Register16::BX, 0x0200);
+
+    let cycles = common::step(&mut cpu);
+    assert_eq!(cpu.get_ip(), 0x0100 + code.len() as u16);
+
+    (cpu.get_register16(Register16::AX), cycles)
+}
+
+#[test]
+fn test_last_segment_override_wins() {
+    // MOV AX, [BX]
+    let (ax, no_prefix_cycles) = run_load(&[0x8B, 0x07]);
+    assert_eq!(ax, 0x5678);
+
+    // CS: MOV AX, [BX]
+    let (ax, cs_cycles) = run_load(&[0x2E, 0x8B, 0x07]);
+    assert_eq!(ax, 0x1234);
+
+    // CS: DS: MOV AX, [BX]. The last override (DS) takes effect.
+    let (ax, cs_ds_cycles) = run_load(&[0x2E, 0x3E, 0x8B, 0x07]);
+    assert_eq!(ax, 0x5678);
+
+    // DS: CS: MOV AX, [BX]. The last override (CS) takes effect.
+    let (ax, ds_cs_cycles) = run_load(&[0x3E, 0x2E, 0x8B, 0x07]);
+    assert_eq!(ax, 0x1234);
+
+    // Each prefix byte is fetched and decoded, so each one adds to the instruction's timing.
+    assert!(cs_cycles > no_prefix_cycles);
+    assert!(cs_ds_cycles > cs_cycles);
+    assert_eq!(cs_ds_cycles, ds_cs_cycles);
+}
+
+/// Run `code` at 1000:0100 and return the cycles it took and the resulting IP.
+fn run_cycles(code: &[u8]) -> (u32, u16) {
+    let mut cpu = common::cpu_8088();
+    common::boot(&mut cpu, code);
+
+    let cycles = common::step(&mut cpu);
+    assert_eq!(cpu.get_register16(Register16::AX), 1);
+    (cycles, cpu.get_ip())
+}
+
+#[test]
+fn test_f1_lock_alias() {
+    // INC AX
+    let (plain_cycles, _) = run_cycles(&[0x40]);
+    // LOCK INC AX
+    let (lock_cycles, lock_ip) = run_cycles(&[0xF0, 0x40]);
+    // F1 INC AX. 0xF1 acts as a LOCK prefix, not as a standalone instruction.
+    let (f1_cycles, f1_ip) = run_cycles(&[0xF1, 0x40]);
+
+    assert_eq!(lock_ip, 0x0102);
+    assert_eq!(f1_ip, 0x0102);
+    assert_eq!(f1_cycles, lock_cycles);
+    assert!(f1_cycles > plain_cycles);
+}
+
+/// Run `code` at 1000:0100 with a memory access breakpoint set at `bp_addr`, and return whether
+/// the breakpoint was hit.
+fn hits_access_breakpoint(code: &[u8], bp_addr: u32) -> bool {
+    let mut cpu = common::cpu_8088();
+
+    // Follow the instruction with a NOP, so we have something to step onto.
+    let mut program = code.to_vec();
+    program.push(0x90);
+    common::boot(&mut cpu, &program);
+    cpu.set_register16(Register16::DS, 0x2000);
+    cpu.set_register16(Register16::BX, 0x0200);
+    cpu.set_breakpoints(vec![BreakPointType::MemAccessFlat(bp_addr)]);
+
+    common::step(&mut cpu);
+
+    // A memory access breakpoint stops execution before the next instruction.
+    let (result, _) = cpu.step(false).unwrap();
+    matches!(result, StepResult::BreakpointHit)
+}
+
+#[test]
+fn test_esc_dummy_read_segment_override() {
+    // ESC D9 [BX] (FLD) performs a dummy read from DS:BX
+    assert!(hits_access_breakpoint(&[0xD9, 0x07], 0x20200));
+    assert!(!hits_access_breakpoint(&[0xD9, 0x07], 0x10200));
+
+    // CS: ESC D9 [BX] reads from CS:BX instead
+    assert!(hits_access_breakpoint(&[0x2E, 0xD9, 0x07], 0x10200));
+    assert!(!hits_access_breakpoint(&[0x2E, 0xD9, 0x07], 0x20200));
+}
@@ -0,0 +1,68 @@
+/*
+    MartyPC
+    https://github.com/dbalsom/martypc
+
+    Copyright 2022-2024 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    ---------------------------------------------------------------------------
+
+    tests::cpu_profile.rs
+
+    Tests for CPU execution profiling.
+
+*/
+
+mod common;
+
+use marty_core::cpu_common::Cpu;
+
+#[test]
+fn test_profile_loop() {
+    let mut cpu = common::cpu_8088();
+
+    // 1000:0100  MOV CX, 3
+    // 1000:0103  NOP
+    // 1000:0104  LOOP 0103
+    // 1000:0106  NOP
+    let program = [0xB9, 0x03, 0x00, 0x90, 0xE2, 0xFD, 0x90];
+    common::boot(&mut cpu, &program);
+    cpu.enable_profiling(true);
+
+    // MOV + 3 * (NOP + LOOP)
+    for _ in 0..7 {
+        cpu.step(false).expect("CPU error");
+        cpu.step_finish(None).expect("CPU error");
+    }
+
+    let profile = cpu.get_profile();
+    assert_eq!(profile.opcodes[0xB9], 1);
+    assert_eq!(profile.opcodes[0x90], 3);
+    assert_eq!(profile.opcodes[0xE2], 3);
+    assert_eq!(profile.total(), 7);
+    assert_eq!(profile.addresses.get(&0x10103), Some(&3));
+    assert_eq!(profile.hot_opcodes(2), vec![(0x90, 3), (0xE2, 3)]);
+
+    // Profiling can be switched off without losing the collected profile.
+    cpu.enable_profiling(false);
+    cpu.step(false).expect("CPU error");
+    assert_eq!(cpu.get_profile().opcodes[0x90], 3);
+    assert_eq!(cpu.get_profile().total(), 7);
+}
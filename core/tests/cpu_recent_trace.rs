@@ -0,0 +1,100 @@
+/*
+    MartyPC
+    https://github.com/dbalsom/martypc
+
+    Copyright 2022-2024 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+
+    tests::cpu_recent_trace.rs
+
+    Tests for the recent instruction trace used for post-mortem dumps.
+
+*/
+
+mod common;
+
+use marty_core::cpu_common::{Cpu, CpuOption};
+
+#[test]
+fn test_recent_trace_evicts_oldest() {
+    let mut cpu = common::cpu_8088();
+
+    // 1000:0100  INC AX
+    // 1000:0101  INC BX
+    // 1000:0102  INC CX
+    // 1000:0103  INC DX
+    // 1000:0104  MOV AX, 1234h
+    // 1000:0107  NOP
+    let program = [0x40, 0x43, 0x41, 0x42, 0xB8, 0x34, 0x12, 0x90];
+    common::boot(&mut cpu, &program);
+    cpu.set_option(CpuOption::RecentTraceDepth(3));
+
+    for _ in 0..6 {
+        common::step(&mut cpu);
+    }
+
+    let trace = cpu.dump_recent_trace();
+    let lines: Vec<&str> = trace.lines().collect();
+
+    // Only the last three instructions are retained, oldest first.
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("[1000:0103] 42"));
+    assert!(lines[1].starts_with("[1000:0104] B8 34 12"));
+    assert!(lines[1].ends_with("mov (opcode B8)"), "{}", lines[1]);
+    assert!(lines[2].starts_with("[1000:0107] 90"));
+    assert!(!trace.contains("[1000:0100]"));
+}
+
+#[test]
+fn test_recent_trace_disabled() {
+    let mut cpu = common::cpu_8088();
+
+    common::boot(&mut cpu, &[0x90]);
+
+    common::step(&mut cpu);
+
+    assert!(cpu.dump_recent_trace().is_empty());
+}
+
+#[test]
+fn test_recent_trace_long_instruction() {
+    let mut cpu = common::cpu_8088();
+
+    // 1000:0100  ES: ES: ES: MOV AX, [1234h]
+    // 1000:0107  NOP
+    let program = [0x26, 0x26, 0x26, 0x8B, 0x06, 0x34, 0x12, 0x90];
+    common::boot(&mut cpu, &program);
+    // A very deep trace must not reserve space for every entry up front.
+    cpu.set_option(CpuOption::RecentTraceDepth(u32::MAX));
+
+    for _ in 0..2 {
+        common::step(&mut cpu);
+    }
+
+    let trace = cpu.dump_recent_trace();
+    let lines: Vec<&str> = trace.lines().collect();
+
+    // Only the first six bytes of an instruction are kept.
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("[1000:0100] 26 26 26 8B 06 34 .."), "{}", lines[0]);
+    assert!(lines[0].ends_with("mov (opcode 8B)"), "{}", lines[0]);
+    assert!(lines[1].starts_with("[1000:0107] 90"), "{}", lines[1]);
+}
@@ -0,0 +1,84 @@
+/*
+    MartyPC
+    https://github.com/dbalsom/martypc
+
+    Copyright 2022-2024 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    ---------------------------------------------------------------------------
+
+    tests::cpu_rep_limit.rs
+
+    Tests for the REP iteration limit, which stops a runaway REP-prefixed
+    instruction with an error.
+
+*/
+
+mod common;
+
+use marty_core::cpu_common::{Cpu, CpuDispatch, CpuError, CpuOption, Register16};
+
+/// Boot REP MOVSB with `cx` iterations, copying from 2000:0000 to 3000:0000.
+fn boot_rep_movsb(cpu: &mut CpuDispatch, cx: u16) {
+    // 1000:0100  REP MOVSB
+    // 1000:0102  NOP
+    common::boot(cpu, &[0xF3, 0xA4, 0x90]);
+    cpu.set_register16(Register16::CX, cx);
+    cpu.set_register16(Register16::DS, 0x2000);
+    cpu.set_register16(Register16::ES, 0x3000);
+    cpu.set_register16(Register16::SI, 0x0000);
+    cpu.set_register16(Register16::DI, 0x0000);
+}
+
+#[test]
+fn test_rep_iteration_limit() {
+    let mut cpu = common::cpu_8088();
+    cpu.set_option(CpuOption::RepIterationLimit(100));
+    boot_rep_movsb(&mut cpu, 0x8000);
+
+    // Each step() runs one iteration. The 101st iteration exceeds the limit.
+    for _ in 0..100 {
+        common::step(&mut cpu);
+    }
+    match cpu.step(false) {
+        Err(CpuError::ExecutionError(address, err)) => {
+            assert_eq!(address, 0x10100);
+            assert!(err.contains("REP iteration limit (100) exceeded"), "{}", err);
+            assert!(err.contains("MOVSB"), "{}", err);
+            assert!(err.contains("[1000:0100]"), "{}", err);
+            assert!(err.contains("CX=7F9B"), "{}", err);
+        }
+        result => panic!("expected an execution error, got {:?}", result.map(|(r, _)| r)),
+    }
+}
+
+#[test]
+fn test_rep_iteration_limit_not_reached() {
+    let mut cpu = common::cpu_8088();
+    cpu.set_option(CpuOption::RepIterationLimit(100));
+    boot_rep_movsb(&mut cpu, 100);
+
+    // A REP that finishes within the limit completes normally.
+    for _ in 0..100 {
+        common::step(&mut cpu);
+    }
+    assert_eq!(cpu.get_register16(Register16::CX), 0);
+    assert_eq!(cpu.get_register16(Register16::DI), 100);
+}
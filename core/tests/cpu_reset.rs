@@ -0,0 +1,66 @@
+/*
+    MartyPC
+    https://github.com/dbalsom/martypc
+
+    Copyright 2022-2024 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    ---------------------------------------------------------------------------
+
+    tests::cpu_reset.rs
+
+    Tests for configurable CPU reset state.
+
+*/
+
+mod common;
+
+use marty_core::cpu_common::{Cpu, CpuResetState, Register16};
+
+#[test]
+fn test_custom_reset_state() {
+    let mut cpu = common::cpu_8088();
+
+    // Default IBM PC reset vector
+    assert_eq!(cpu.get_register16(Register16::CS), 0xFFFF);
+    assert_eq!(cpu.get_ip(), 0x0000);
+
+    cpu.set_reset_state(CpuResetState {
+        cs: Some(0xFD80),
+        ip: Some(0x0000),
+        flags: Some(0x0000),
+        ss: Some(0x0030),
+        sp: Some(0x0100),
+        ..Default::default()
+    });
+
+    // Dirty some state so we can see reset clear it.
+    cpu.set_register16(Register16::AX, 0x1234);
+    cpu.set_flags(0xFFFF);
+    cpu.reset();
+
+    assert_eq!(cpu.get_register16(Register16::CS), 0xFD80);
+    assert_eq!(cpu.get_ip(), 0x0000);
+    assert_eq!(cpu.get_register16(Register16::SS), 0x0030);
+    assert_eq!(cpu.get_register16(Register16::SP), 0x0100);
+    assert_eq!(cpu.get_register16(Register16::AX), 0x0000);
+    // Only the reserved flag bits remain set.
+    assert_eq!(cpu.get_flags(), 0xF002);
+}
@@ -0,0 +1,121 @@
+/*
+    MartyPC
+    https://github.com/dbalsom/martypc
+
+    Copyright 2022-2024 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    ---------------------------------------------------------------------------
+
+    tests::pacing_warp.rs
+
+    Tests that warp mode changes only host pacing: the device time deltas
+    produced while running a fixed number of instructions match the executed
+    cycles whether or not warp mode is enabled.
+
+*/
+
+mod common;
+
+use std::time::Duration;
+
+use marty_core::{bus::ClockFactor, cpu_common::CpuDispatch, pacing};
+
+/// The IBM PC system crystal, in MHz, and the 8088's clock divisor.
+const SYSTEM_CRYSTAL: f64 = 14.31818;
+const CPU_FACTOR: ClockFactor = ClockFactor::Divisor(3);
+
+const BATCHES: usize = 8;
+const BATCH_INSTRUCTIONS: u64 = 100;
+
+/// Device time handed out while running, along with the CPU cycles executed, as Machine::run
+/// counts them from the values returned by step().
+#[derive(Default)]
+struct DeviceTime {
+    us_deltas: Vec<f64>,
+    ticks: u64,
+    cpu_cycles: u64,
+}
+
+/// Run one batch of instructions the way Machine::run does, passing each step's cycles to the
+/// devices as a microsecond delta. Returns the number of instructions executed, or 0 once all
+/// batches have run.
+fn run_batch(cpu: &mut CpuDispatch, time: &mut DeviceTime, batches_run: &mut usize) -> u64 {
+    if *batches_run == BATCHES {
+        return 0;
+    }
+    *batches_run += 1;
+
+    for _ in 0..BATCH_INSTRUCTIONS {
+        let cycles = common::step(cpu);
+        time.cpu_cycles += cycles as u64;
+        time.us_deltas.push(pacing::cpu_cycles_to_us(cycles, CPU_FACTOR, SYSTEM_CRYSTAL));
+        time.ticks += pacing::cpu_cycles_to_system_ticks(cycles, CPU_FACTOR) as u64;
+    }
+    BATCH_INSTRUCTIONS
+}
+
+/// Run every batch with warp mode on or off, calling run_batches() once per frontend update.
+fn run(warp: bool) -> DeviceTime {
+    let mut cpu = common::cpu_8088();
+
+    // 1000:0100  INC AX
+    // 1000:0101  ADD BX, AX
+    // 1000:0103  JMP 0100
+    common::boot(&mut cpu, &[0x40, 0x01, 0xC3, 0xEB, 0xFB]);
+
+    let mut time = DeviceTime::default();
+    let mut batches_run = 0;
+    let mut instructions = 0;
+    // A generous budget, so that warp mode only stops once the batches run out.
+    let budget = Duration::from_secs(60);
+    while batches_run < BATCHES {
+        instructions += pacing::run_batches(warp, budget, || run_batch(&mut cpu, &mut time, &mut batches_run));
+    }
+    assert_eq!(instructions, BATCHES as u64 * BATCH_INSTRUCTIONS);
+    time
+}
+
+#[test]
+fn test_warp_device_time() {
+    let normal = run(false);
+    let warp = run(true);
+
+    for time in [&normal, &warp] {
+        assert!(time.cpu_cycles > 0);
+        let us: f64 = time.us_deltas.iter().sum();
+        let expected_us = time.cpu_cycles as f64 * 3.0 / SYSTEM_CRYSTAL;
+        assert!((us - expected_us).abs() < 1e-6, "{} us for {} cycles", us, time.cpu_cycles);
+        assert_eq!(time.ticks, time.cpu_cycles * 3);
+    }
+
+    // Warp mode changes only pacing, so devices see exactly the same deltas.
+    assert_eq!(warp.cpu_cycles, normal.cpu_cycles);
+    assert_eq!(warp.us_deltas, normal.us_deltas);
+}
+
+#[test]
+fn test_warp_discards_samples() {
+    let mut queued = Vec::new();
+
+    assert!(pacing::route_sample(false, 0.5, |s| queued.push(s)));
+    assert!(!pacing::route_sample(true, 0.25, |s| queued.push(s)));
+    assert_eq!(queued, vec![0.5]);
+}
@@ -205,6 +205,7 @@ impl Emulator {
         {
             self.config.emulator.warpspeed = false;
         }
+        self.machine.set_warp_mode(self.config.emulator.warpspeed);
 
         // Set up cycle trace viewer
         self.gui
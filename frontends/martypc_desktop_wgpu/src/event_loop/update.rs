@@ -37,7 +37,11 @@ use frontend_common::{
     constants::{LONG_NOTIFICATION_TIME, NORMAL_NOTIFICATION_TIME, SHORT_NOTIFICATION_TIME},
     timestep_manager::{MachinePerfStats, TimestepManager},
 };
-use marty_core::{bus::DeviceEvent, machine::MachineEvent};
+use marty_core::{
+    bus::DeviceEvent,
+    machine::MachineEvent,
+    pacing::{self, WARP_UPDATE_BUDGET},
+};
 use videocard_renderer::RendererEvent;
 
 use crate::{
@@ -60,7 +64,17 @@ pub fn process_update(emu: &mut Emulator, tm: &mut TimestepManager, elwt: &Event
         },
         |emuc, cycles| {
             // Per emu update freq
-            emuc.machine.run(cycles, &mut emuc.exec_control.borrow_mut());
+            let warp = emuc.machine.warp_mode();
+            pacing::run_batches(warp, WARP_UPDATE_BUDGET, || {
+                emuc.machine.run(cycles, &mut emuc.exec_control.borrow_mut())
+            });
+            if warp {
+                // Don't frame limit in warp mode.
+                None
+            }
+            else {
+                emuc.machine.primary_videocard().map(|vc| vc.get_frame_count())
+            }
         },
         |emuc, tmc, &perf| {
             emuc.perf = perf;
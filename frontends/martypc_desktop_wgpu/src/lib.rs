@@ -700,6 +700,7 @@ pub fn run() {
     timestep_manager.set_cpu_mhz(machine.get_cpu_mhz());
     timestep_manager.set_emu_update_rate(highest_rate);
     timestep_manager.set_emu_render_rate(highest_rate);
+    timestep_manager.set_frame_limit(config.emulator.frame_limit);
 
     let gui_options = DisplayManagerGuiOptions {
         enabled: !config.gui.disabled,
@@ -151,6 +151,8 @@ pub struct Emulator {
     #[serde(default)]
     pub warpspeed: bool,
     #[serde(default)]
+    pub frame_limit: Option<u32>,
+    #[serde(default)]
     pub title_hacks: bool,
     #[serde(default)]
     pub debug_mode: bool,
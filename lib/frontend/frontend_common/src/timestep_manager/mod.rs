@@ -38,6 +38,7 @@ const UPS_CAP: u64 = 1000; // Maximum number of window manager updates per secon
 const UPS_MIN_DURATION: Duration = Duration::from_millis(1000 / UPS_CAP as u64); // Minimum duration between window manager updates
 const DEFAULT_EMU_FPS_TARGET: u32 = 60; // Default rendering FPS for the emulator
 const FRAME_HISTORY_LEN: usize = 60; // Number of frames of history to keep
+const FRAME_LIMIT_MAX_LAG: u32 = 4; // Number of frames the frame limiter may fall behind before it resynchronizes
 
 #[derive(Copy, Clone, Default)]
 pub struct FrameEntry {
@@ -120,6 +121,65 @@ impl HertzEvent {
     }
 }
 
+/// Paces emulation to a target frame rate using the frame count reported by the primary video card,
+/// independent of audio. This is useful when there is no audio device to pace the machine.
+#[derive(Copy, Clone)]
+pub struct FrameLimiter {
+    target: Duration,
+    last_frame: Option<u64>,
+    deadline: Option<Instant>,
+}
+
+impl FrameLimiter {
+    pub fn new(fps: u32) -> Self {
+        Self {
+            target: Duration::from_micros(1_000_000 / fps.max(1) as u64),
+            last_frame: None,
+            deadline: None,
+        }
+    }
+
+    pub fn set_fps(&mut self, fps: u32) {
+        self.target = Duration::from_micros(1_000_000 / fps.max(1) as u64);
+    }
+
+    /// Report the video card's frame count as of host time `now`. If the emulator has completed
+    /// frames ahead of schedule, returns the duration the caller should wait before continuing.
+    /// If the emulator falls too far behind, the schedule is reset rather than running to catch up.
+    pub fn update(&mut self, frame_count: u64, now: Instant) -> Option<Duration> {
+        let last_frame = match self.last_frame {
+            Some(last_frame) => last_frame,
+            None => {
+                // First report establishes the schedule.
+                self.last_frame = Some(frame_count);
+                self.deadline = Some(now);
+                return None;
+            }
+        };
+
+        if frame_count == last_frame {
+            return None;
+        }
+        let new_frames = frame_count.saturating_sub(last_frame) as u32;
+        self.last_frame = Some(frame_count);
+
+        let deadline = self.deadline.unwrap_or(now) + self.target * new_frames;
+        if deadline > now {
+            self.deadline = Some(deadline);
+            Some(deadline - now)
+        }
+        else {
+            if now - deadline > self.target * FRAME_LIMIT_MAX_LAG {
+                self.deadline = Some(now);
+            }
+            else {
+                self.deadline = Some(deadline);
+            }
+            None
+        }
+    }
+}
+
 #[derive(Copy, Clone, Default)]
 pub struct PerfStats {
     pub wm_ups: PerfCounter,  // Number of updates per second from the window manager
@@ -200,6 +260,7 @@ pub struct TimestepManager {
     perf_stats: PerfStats,
     total_running_time: Duration,
     frame_due: bool,
+    frame_limiter: Option<FrameLimiter>,
 }
 
 impl Default for TimestepManager {
@@ -226,6 +287,7 @@ impl Default for TimestepManager {
             perf_stats: PerfStats::default(),
 
             frame_due: false,
+            frame_limiter: None,
         }
     }
 }
@@ -247,6 +309,8 @@ impl TimestepManager {
     /// When a second has elapsed, the 'machine_callback' is called to retrieve the current
     /// CPU cycle count, system tick count, instruction count, and optionally the number of
     /// rendered frames from the primary video card (if present).
+    /// The 'emu_update_callback' returns the primary video card's frame count, if any, which
+    /// drives the frame limiter when one is set.
     pub fn wm_update<E, F, G, H>(
         &mut self,
         emu: &mut E,
@@ -255,7 +319,7 @@ impl TimestepManager {
         mut emu_render_callback: H,
     ) where
         F: FnOnce(&mut E) -> MachinePerfStats,
-        G: FnMut(&mut E, u32),
+        G: FnMut(&mut E, u32) -> Option<u64>,
         H: FnMut(&mut E, &TimestepManager, &PerfSnapshot),
     {
         if !self.init {
@@ -277,9 +341,15 @@ impl TimestepManager {
         if self.emu_update_rate.tick(elapsed) {
             self.last_frame_instant = Instant::now();
             let emu_start = Instant::now();
-            emu_update_callback(emu, self.cpu_cycle_update_target);
+            let frame_count = emu_update_callback(emu, self.cpu_cycle_update_target);
             self.perf_stats.emu_ups.tick();
             self.perf_stats.emu_time = emu_start.elapsed();
+
+            if let (Some(limiter), Some(frame_count)) = (&mut self.frame_limiter, frame_count) {
+                if let Some(wait) = limiter.update(frame_count, Instant::now()) {
+                    thread::sleep(wait);
+                }
+            }
         }
 
         // Handle emu frame render
@@ -350,6 +420,12 @@ impl TimestepManager {
         self.cpu_mhz = mhz;
     }
 
+    /// Set a target frame rate to pace emulation by the primary video card's frame count, or None
+    /// to disable frame limiting.
+    pub fn set_frame_limit(&mut self, fps: Option<u32>) {
+        self.frame_limiter = fps.map(FrameLimiter::new);
+    }
+
     pub fn set_emu_update_rate(&mut self, fps: u32) {
         self.emu_update_rate.set(fps);
     }
@@ -366,3 +442,34 @@ impl TimestepManager {
         (&self.perf_stats, self.frame_history.as_vec())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_limiter() {
+        let start = Instant::now();
+        let mut now = start;
+        let mut limiter = FrameLimiter::new(60);
+
+        // The machine completes a frame every 2ms of host time, far faster than 60 FPS. Advance the
+        // mock clock by any wait the limiter requests.
+        let mut frames = 0;
+        while now - start < Duration::from_secs(1) {
+            now += Duration::from_millis(2);
+            frames += 1;
+            if let Some(wait) = limiter.update(frames, now) {
+                now += wait;
+            }
+        }
+        assert!((59..=61).contains(&frames), "{} frames in one second", frames);
+
+        // A machine slower than the target is never delayed.
+        let mut limiter = FrameLimiter::new(60);
+        for frame in 0..60 {
+            now += Duration::from_millis(20);
+            assert_eq!(limiter.update(frame, now), None);
+        }
+    }
+}